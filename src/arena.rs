@@ -0,0 +1,758 @@
+use std::fmt::Display;
+
+use crate::{
+    game_player::{play_parallel, Agent, Match},
+    game_state::{outcome::Payoff, player::TwoPlayer, GameState},
+    rng::Rng,
+};
+
+/// The number of standard deviations on either side of an estimate for a 95% confidence
+/// interval.
+const Z_95: f64 = 1.959964;
+
+/// Converts an expected score to the Elo rating difference that predicts it.
+pub fn score_to_elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+/// Converts an Elo rating difference to the expected score of the stronger side.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// The games an agent won, drew and lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Record {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Record {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The average score, counting a win as 1 and a draw as 1/2.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// The Elo difference to the opponents implied by the score, with a 95% confidence
+    /// interval. The interval is computed for the score, from the variance of the results of
+    /// single games, and then converted to Elo, so it isn't symmetric. Perfect scores give
+    /// infinite ratings.
+    pub fn elo(&self) -> Rating {
+        let n = self.games() as f64;
+        let score = self.score();
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / n;
+        let margin = Z_95 * (variance / n).sqrt();
+        Rating {
+            elo: score_to_elo(score),
+            lower: score_to_elo(score - margin),
+            upper: score_to_elo(score + margin),
+        }
+    }
+
+    /// The same games seen from the opponent's side.
+    pub fn reversed(&self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    /// Records a game with the given payoff, which is a win if positive and a loss if
+    /// negative.
+    pub fn push(&mut self, payoff: f64) {
+        if payoff > 0.0 {
+            self.wins += 1;
+        } else if payoff < 0.0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    fn add(&mut self, other: &Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+{}={}-{}", self.wins, self.draws, self.losses)
+    }
+}
+
+/// A rating on the Elo scale with a 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0} [{:.0}, {:.0}]", self.elo, self.lower, self.upper)
+    }
+}
+
+/// Which agents play each other in an Arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every agent plays every other agent.
+    RoundRobin,
+    /// The agent with the given index plays every other agent, who don't play each other.
+    Gauntlet { challenger: usize },
+}
+
+/// Runs matches between registered agents to compare them statistically.
+///
+/// Every pairing plays the given number of games, and the agents alternate seats from one game
+/// to the next, so each moves first in half of the games. Outcomes are scored by the sign of
+/// their Payoff.
+pub struct Arena<'a, G>
+where
+    G: GameState<Player = TwoPlayer>,
+{
+    root: G,
+    names: Vec<String>,
+    agents: Vec<Box<dyn Agent<G> + 'a>>,
+}
+
+impl<'a, G> Arena<'a, G>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Clone,
+{
+    /// Every game starts from root.
+    pub fn new(root: G) -> Self {
+        Self {
+            root,
+            names: vec![],
+            agents: vec![],
+        }
+    }
+
+    /// Registers an agent under the given name and returns its index.
+    pub fn add(&mut self, name: &str, agent: impl Agent<G> + 'a) -> usize {
+        self.names.push(name.to_string());
+        self.agents.push(Box::new(agent));
+        self.agents.len() - 1
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Plays games_per_pairing games between every pairing of the format.
+    pub fn run(&mut self, format: Format, games_per_pairing: usize) -> Standings {
+        let n = self.agents.len();
+        let mut records = vec![vec![Record::default(); n]; n];
+        for (i, j) in pairings(format, n) {
+            let record = self.play_pairing(i, j, games_per_pairing);
+            records[i][j].add(&record);
+            records[j][i].add(&record.reversed());
+        }
+        Standings {
+            names: self.names.clone(),
+            records,
+        }
+    }
+
+    /// Plays the games between agents i and j and returns the record of i.
+    fn play_pairing(&mut self, i: usize, j: usize, games: usize) -> Record {
+        let mut record = Record::default();
+        for game in 0..games {
+            let payoff = self.play_game(i, j, game);
+            record.push(payoff);
+        }
+        record
+    }
+
+    /// Plays one game between agents i and j and returns the payoff of i. Agent i is Player 0
+    /// in the even games.
+    fn play_game(&mut self, i: usize, j: usize, game: usize) -> f64 {
+        let (low, high) = self.agents.split_at_mut(i.max(j));
+        let (agent_i, agent_j) = if i < j {
+            (&mut low[i], &mut high[0])
+        } else {
+            (&mut high[0], &mut low[j])
+        };
+        let i_player = TwoPlayer::new(game.is_multiple_of(2));
+        let mut game_match = if i_player.index() == 0 {
+            Match::new(self.root.clone(), agent_i.as_mut(), agent_j.as_mut())
+        } else {
+            Match::new(self.root.clone(), agent_j.as_mut(), agent_i.as_mut())
+        };
+        let (_, outcome) = game_match.play();
+        outcome.payoff(&i_player)
+    }
+
+    /// Plays the candidate against the baseline, alternating seats, until a sequential
+    /// probability ratio test decides whether the candidate is elo0 or elo1 stronger, or until
+    /// max_games have been played.
+    pub fn sprt(&mut self, candidate: usize, baseline: usize, config: &SprtConfig) -> Sprt {
+        assert!(candidate != baseline, "An agent can't play itself.");
+        let mut record = Record::default();
+        let (lower, upper) = config.bounds();
+        let mut llr = 0.0;
+        while record.games() < config.max_games {
+            let payoff = self.play_game(candidate, baseline, record.games());
+            record.push(payoff);
+            llr = config.llr(&record);
+            if llr <= lower || llr >= upper {
+                break;
+            }
+        }
+        let decision = if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Inconclusive
+        };
+        Sprt {
+            decision,
+            record,
+            llr,
+            lower,
+            upper,
+        }
+    }
+}
+
+/// The pairs of agents that play each other in the format, out of n agents.
+fn pairings(format: Format, n: usize) -> Vec<(usize, usize)> {
+    match format {
+        Format::RoundRobin => (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect(),
+        Format::Gauntlet { challenger } => {
+            assert!(challenger < n, "The challenger isn't registered.");
+            (0..n)
+                .filter(|&i| i != challenger)
+                .map(|i| (challenger, i))
+                .collect()
+        }
+    }
+}
+
+type AgentFactory<'a, G> = Box<dyn Fn(u64) -> Box<dyn Agent<G> + 'a> + Sync + 'a>;
+
+/// An Arena that plays its games on several threads with play_parallel.
+///
+/// Agents are registered as factories, which build a fresh agent from a seed inside every
+/// game, so that agents don't need to be Send and no state is shared between games. The games
+/// and seats are the same as in Arena::run, and the results only depend on the seed, not on the
+/// number of workers.
+pub struct ParallelArena<'a, G>
+where
+    G: GameState<Player = TwoPlayer>,
+{
+    root: G,
+    names: Vec<String>,
+    factories: Vec<AgentFactory<'a, G>>,
+}
+
+impl<'a, G> ParallelArena<'a, G>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer> + Send> + Clone + Send + Sync,
+{
+    /// Every game starts from root.
+    pub fn new(root: G) -> Self {
+        Self {
+            root,
+            names: vec![],
+            factories: vec![],
+        }
+    }
+
+    /// Registers an agent factory under the given name and returns its index.
+    pub fn add<A, F>(&mut self, name: &str, factory: F) -> usize
+    where
+        A: Agent<G> + 'a,
+        F: Fn(u64) -> A + Sync + 'a,
+    {
+        self.names.push(name.to_string());
+        self.factories.push(Box::new(move |seed| {
+            Box::new(factory(seed)) as Box<dyn Agent<G> + 'a>
+        }));
+        self.factories.len() - 1
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Plays games_per_pairing games between every pairing of the format on the given number of
+    /// workers. The two agents of a game are built from seeds drawn from the seed of the game.
+    pub fn run(
+        &self,
+        format: Format,
+        games_per_pairing: usize,
+        workers: usize,
+        seed: u64,
+    ) -> Standings {
+        let n = self.factories.len();
+        let pairings = pairings(format, n);
+        // Agent i of a pairing (i, j) is Player 0 in the even games, as in Arena::play_game.
+        let i_player = |game: usize| TwoPlayer::new((game % games_per_pairing).is_multiple_of(2));
+        let games = pairings.len() * games_per_pairing;
+        let results = play_parallel(&self.root, games, workers, seed, |game, seed| {
+            let (i, j) = pairings[game / games_per_pairing];
+            let mut rng = Rng::new(seed);
+            let agent_i = (self.factories[i])(rng.next_u64());
+            let agent_j = (self.factories[j])(rng.next_u64());
+            if i_player(game).index() == 0 {
+                (agent_i, agent_j)
+            } else {
+                (agent_j, agent_i)
+            }
+        });
+        let mut records = vec![vec![Record::default(); n]; n];
+        for (game, (_, outcome)) in results.iter().enumerate() {
+            let (i, j) = pairings[game / games_per_pairing];
+            let mut record = Record::default();
+            record.push(outcome.payoff(&i_player(game)));
+            records[i][j].add(&record);
+            records[j][i].add(&record.reversed());
+        }
+        Standings {
+            names: self.names.clone(),
+            records,
+        }
+    }
+}
+
+/// The hypotheses and error rates of a sequential probability ratio test. H0 is that the
+/// candidate is elo0 stronger than the baseline and H1 that it is elo1 stronger, where elo1 is
+/// larger. alpha is the probability of accepting H1 when H0 holds and beta the probability of
+/// accepting H0 when H1 holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SprtConfig {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    /// The test gives up and is Inconclusive after this many games.
+    pub max_games: usize,
+}
+
+impl Default for SprtConfig {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+            max_games: 100_000,
+        }
+    }
+}
+
+impl SprtConfig {
+    /// The log likelihood ratios at which the test accepts H0 and H1.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// The log likelihood ratio of H1 to H0 given the record, in the normal approximation to
+    /// the distribution of the score.
+    ///
+    /// The variance includes one virtual win and one virtual loss, so that it isn't 0 while
+    /// every game has had the same result.
+    pub fn llr(&self, record: &Record) -> f64 {
+        let n = record.games() as f64;
+        let score = record.score();
+        let variance = (record.wins as f64 * (1.0 - score).powi(2)
+            + record.draws as f64 * (0.5 - score).powi(2)
+            + record.losses as f64 * score.powi(2)
+            + (1.0 - score).powi(2)
+            + score.powi(2))
+            / (n + 2.0);
+        let (score0, score1) = (elo_to_score(self.elo0), elo_to_score(self.elo1));
+        n * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// The candidate is elo1 stronger.
+    AcceptH1,
+    /// The candidate is at most elo0 stronger.
+    AcceptH0,
+    /// The test reached max_games without a decision.
+    Inconclusive,
+}
+
+/// The result of Arena::sprt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub decision: SprtDecision,
+    /// The record of the candidate against the baseline.
+    pub record: Record,
+    pub llr: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The results of Arena::run: records[i][j] is the record of agent i against agent j.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standings {
+    pub names: Vec<String>,
+    pub records: Vec<Vec<Record>>,
+}
+
+impl Standings {
+    /// The record of the agent against all of its opponents.
+    pub fn total(&self, agent: usize) -> Record {
+        let mut total = Record::default();
+        for record in &self.records[agent] {
+            total.add(record);
+        }
+        total
+    }
+
+    /// The Elo performance of every agent against the opponents it played, from its total
+    /// score. This compares each agent to its own field, so in a gauntlet only the
+    /// challenger's rating is against everyone else.
+    pub fn elo(&self) -> Vec<Rating> {
+        (0..self.names.len())
+            .map(|agent| self.total(agent).elo())
+            .collect()
+    }
+
+    /// Fits a Bradley-Terry model, in which agent i beats agent j with probability
+    /// gamma_i / (gamma_i + gamma_j), and returns the strengths on the Elo scale with a mean of
+    /// 0. A draw counts as half a win for each side. The confidence intervals come from the
+    /// Fisher information of the fit.
+    ///
+    /// Every pairing that played also gets one virtual draw, which keeps the ratings of agents
+    /// that won or lost every game finite. Agents that played no games are rated 0 with an
+    /// infinite interval.
+    pub fn bradley_terry(&self) -> Vec<Rating> {
+        let n = self.names.len();
+        let games = |i: usize, j: usize| {
+            let played = self.records[i][j].games();
+            if played == 0 {
+                0.0
+            } else {
+                played as f64 + 1.0
+            }
+        };
+        let wins: Vec<f64> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .filter(|&j| games(i, j) > 0.0)
+                    .map(|j| {
+                        let record = &self.records[i][j];
+                        record.wins as f64 + (record.draws as f64 + 1.0) / 2.0
+                    })
+                    .sum()
+            })
+            .collect();
+        let played: Vec<bool> = (0..n).map(|i| wins[i] > 0.0).collect();
+        // The minorization-maximization updates of Hunter (2004).
+        let mut gamma = vec![1.0; n];
+        for _ in 0..10_000 {
+            let mut next = gamma.clone();
+            for i in (0..n).filter(|&i| played[i]) {
+                let denominator: f64 = (0..n).map(|j| games(i, j) / (gamma[i] + gamma[j])).sum();
+                next[i] = wins[i] / denominator;
+            }
+            let log_mean = next
+                .iter()
+                .zip(&played)
+                .filter(|(_, &played)| played)
+                .map(|(g, _)| g.ln())
+                .sum::<f64>()
+                / played.iter().filter(|&&played| played).count().max(1) as f64;
+            for g in &mut next {
+                *g /= log_mean.exp();
+            }
+            let change = next
+                .iter()
+                .zip(&gamma)
+                .map(|(a, b)| (a.ln() - b.ln()).abs())
+                .fold(0.0, f64::max);
+            gamma = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        // The Fisher information of the log strengths is a weighted graph Laplacian. Its
+        // pseudo-inverse, the covariance under the constraint that the mean is 0, is
+        // (L + J / n)^-1 - J / n where J is the matrix of ones.
+        let mut information = vec![vec![1.0 / n as f64; n]; n];
+        for i in 0..n {
+            for j in (0..n).filter(|&j| j != i) {
+                let p = gamma[i] / (gamma[i] + gamma[j]);
+                let weight = games(i, j) * p * (1.0 - p);
+                information[i][i] += weight;
+                information[i][j] -= weight;
+            }
+        }
+        let covariance = invert(information);
+        let scale = 400.0 / 10f64.ln();
+        (0..n)
+            .map(|i| {
+                if !played[i] {
+                    return Rating {
+                        elo: 0.0,
+                        lower: f64::NEG_INFINITY,
+                        upper: f64::INFINITY,
+                    };
+                }
+                let elo = scale * gamma[i].ln();
+                let margin = Z_95 * scale * (covariance[i][i] - 1.0 / n as f64).max(0.0).sqrt();
+                Rating {
+                    elo,
+                    lower: elo - margin,
+                    upper: elo + margin,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Inverts a positive definite matrix by Gauss-Jordan elimination.
+fn invert(mut matrix: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+            .expect("The column isn't empty.");
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let divisor = matrix[column][column];
+        for k in 0..n {
+            matrix[column][k] /= divisor;
+            inverse[column][k] /= divisor;
+        }
+        for row in (0..n).filter(|&row| row != column) {
+            let factor = matrix[row][column];
+            for k in 0..n {
+                matrix[row][k] -= factor * matrix[column][k];
+                inverse[row][k] -= factor * inverse[column][k];
+            }
+        }
+    }
+    inverse
+}
+
+/// A crosstable of the records, followed by every agent's total, Elo performance and
+/// Bradley-Terry rating.
+impl Display for Standings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(5);
+        let cells: Vec<Vec<String>> = self
+            .records
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, record)| {
+                        if i == j || record.games() == 0 {
+                            "-".to_string()
+                        } else {
+                            record.to_string()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let cell_width = cells
+            .iter()
+            .flatten()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max(width);
+        write!(f, "{:width$}", "")?;
+        for name in &self.names {
+            write!(f, " {:>cell_width$}", name)?;
+        }
+        writeln!(f)?;
+        for (name, row) in self.names.iter().zip(&cells) {
+            write!(f, "{:width$}", name)?;
+            for cell in row {
+                write!(f, " {:>cell_width$}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        let elo = self.elo();
+        let bradley_terry = self.bradley_terry();
+        writeln!(
+            f,
+            "{:width$} {:>12} {:>6} {:>20} {:>20}",
+            "", "record", "score", "elo", "bradley-terry"
+        )?;
+        for (i, name) in self.names.iter().enumerate() {
+            let total = self.total(i);
+            writeln!(
+                f,
+                "{:width$} {:>12} {:>6.3} {:>20} {:>20}",
+                name,
+                total.to_string(),
+                total.score(),
+                elo[i].to_string(),
+                bradley_terry[i].to_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arena::{
+            elo_to_score, score_to_elo, Arena, Format, ParallelArena, Record, SprtConfig,
+            SprtDecision,
+        },
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_player::StrategyAgent,
+        games::tic_tac_toe::{Piece, TicTacToe},
+        strategy::GreedyStrategy,
+    };
+
+    #[test]
+    fn elo_of_a_record() {
+        let record = Record {
+            wins: 30,
+            draws: 0,
+            losses: 10,
+        };
+        assert!((score_to_elo(0.75) - 190.85).abs() < 0.01);
+        let rating = record.elo();
+        assert_eq!(rating.elo, score_to_elo(0.75));
+        assert!(rating.lower < rating.elo && rating.elo < rating.upper);
+        // Converting the interval to Elo stretches the side closer to a perfect score.
+        assert!(rating.upper - rating.elo > rating.elo - rating.lower);
+        assert!((record.reversed().elo().elo + rating.elo).abs() < 1e-9);
+        assert!((elo_to_score(rating.elo) - 0.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn round_robin_ranks_minimax_first() {
+        let mut arena = Arena::new(TicTacToe::new(Piece::X));
+        let minimax = arena.add(
+            "minimax",
+            StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy),
+        );
+        for seed in 0..3 {
+            arena.add(
+                &format!("random{}", seed),
+                StrategyAgent::new(RandomEvaluator::new(seed), GreedyStrategy),
+            );
+        }
+        let standings = arena.run(Format::RoundRobin, 30);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(standings.records[i][j], standings.records[j][i].reversed());
+            }
+        }
+        assert_eq!(standings.total(minimax).games(), 90);
+        assert_eq!(standings.total(minimax).losses, 0);
+        let ratings = standings.bradley_terry();
+        assert!(ratings.iter().map(|rating| rating.elo).sum::<f64>().abs() < 1e-6);
+        for rating in &ratings[1..] {
+            assert!(rating.upper < ratings[minimax].lower, "{}", standings);
+            assert!(rating.lower < rating.elo && rating.elo < rating.upper);
+        }
+        assert!(standings.to_string().contains("minimax"));
+    }
+
+    #[test]
+    fn gauntlet_only_plays_the_challenger() {
+        let mut arena = Arena::new(TicTacToe::new(Piece::X));
+        for seed in 0..3 {
+            arena.add(
+                &format!("random{}", seed),
+                StrategyAgent::new(RandomEvaluator::new(seed), GreedyStrategy),
+            );
+        }
+        let standings = arena.run(Format::Gauntlet { challenger: 1 }, 10);
+        assert_eq!(standings.total(1).games(), 20);
+        assert_eq!(standings.records[0][2].games(), 0);
+        assert_eq!(standings.bradley_terry().len(), 3);
+    }
+
+    #[test]
+    fn parallel_arena_does_not_depend_on_workers() {
+        let mut arena = ParallelArena::new(TicTacToe::new(Piece::X));
+        let minimax = arena.add("minimax", |_| {
+            StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy)
+        });
+        for _ in 0..2 {
+            arena.add("random", |seed| {
+                StrategyAgent::new(RandomEvaluator::new(seed), GreedyStrategy)
+            });
+        }
+        let standings = arena.run(Format::RoundRobin, 20, 1, 5);
+        assert_eq!(standings.total(minimax).games(), 40);
+        assert_eq!(standings.total(minimax).losses, 0);
+        assert!(standings.total(minimax).wins > 20, "{}", standings);
+        assert_eq!(standings.records[1][2], standings.records[2][1].reversed());
+        assert_eq!(arena.run(Format::RoundRobin, 20, 4, 5), standings);
+        assert_eq!(arena.run(Format::RoundRobin, 20, 64, 5), standings);
+    }
+
+    #[test]
+    fn sprt_decides_early() {
+        let mut arena = Arena::new(TicTacToe::new(Piece::X));
+        let minimax = arena.add(
+            "minimax",
+            StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy),
+        );
+        let random0 = arena.add(
+            "random0",
+            StrategyAgent::new(RandomEvaluator::new(0), GreedyStrategy),
+        );
+        let random1 = arena.add(
+            "random1",
+            StrategyAgent::new(RandomEvaluator::new(1), GreedyStrategy),
+        );
+        let config = SprtConfig {
+            elo0: 0.0,
+            elo1: 100.0,
+            ..Default::default()
+        };
+        let stronger = arena.sprt(minimax, random0, &config);
+        assert_eq!(stronger.decision, SprtDecision::AcceptH1);
+        assert!(stronger.record.games() < 100, "{:?}", stronger);
+        assert!(stronger.llr >= stronger.upper);
+        // Two random players are equally strong, so the candidate isn't 100 Elo stronger.
+        let equal = arena.sprt(random0, random1, &config);
+        assert_eq!(equal.decision, SprtDecision::AcceptH0, "{:?}", equal);
+        let config = SprtConfig {
+            max_games: 4,
+            ..config
+        };
+        let short = arena.sprt(random0, random1, &config);
+        assert_eq!(short.decision, SprtDecision::Inconclusive);
+        assert_eq!(short.record.games(), 4);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    hash::Hash,
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem,
+    path::Path,
+};
+
+/// A hash map that holds at most capacity entries, for the caches of searching evaluators.
+///
+/// Entries live in two generations. New and recently read entries go into the young one, and
+/// once it holds half the capacity, the old generation is evicted and the young one takes its
+/// place. Reading an old entry moves it back into the young generation, so entries that are
+/// still in use survive, approximating least recently used eviction without any bookkeeping per
+/// read.
+#[derive(Debug, Clone)]
+pub struct BoundedCache<K, V> {
+    young: HashMap<K, V>,
+    old: HashMap<K, V>,
+    capacity: usize,
+    evictions: u64,
+}
+
+impl<K, V> Default for BoundedCache<K, V> {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl<K, V> BoundedCache<K, V> {
+    /// A cache of at most capacity entries, which must be at least 2.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity >= 2, "The capacity must be at least 2.");
+        Self {
+            young: HashMap::new(),
+            old: HashMap::new(),
+            capacity,
+            evictions: 0,
+        }
+    }
+
+    /// A cache that never evicts anything.
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.young.len() + self.old.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of entries evicted so far.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    pub fn clear(&mut self) {
+        self.young.clear();
+        self.old.clear();
+    }
+}
+
+impl<K, V> BoundedCache<K, V>
+where
+    K: Hash + Eq,
+    V: Clone,
+{
+    /// Returns the value of key, keeping it in the cache for longer.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        if let Some(value) = self.young.get(key) {
+            return Some(value.clone());
+        }
+        let (key, value) = self.old.remove_entry(key)?;
+        self.insert(key, value.clone());
+        Some(value)
+    }
+
+    /// Inserts or replaces the value of key, evicting the old generation if the young one is
+    /// full.
+    pub fn insert(&mut self, key: K, value: V) {
+        if !self.young.contains_key(&key) {
+            self.old.remove(&key);
+            if self.young.len() >= self.capacity / 2 {
+                self.evictions += self.old.len() as u64;
+                self.old = mem::take(&mut self.young);
+            }
+        }
+        self.young.insert(key, value);
+    }
+}
+
+impl<K, V> BoundedCache<K, V>
+where
+    K: Hash + Eq + Encode,
+    V: Clone + Encode,
+{
+    /// Writes the cache to the file at path, encoded as below.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.encode(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads a cache written by save, with the capacity it was saved with.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::decode(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// A magic number, then the capacity, the number of entries and every entry.
+impl<K, V> Encode for BoundedCache<K, V>
+where
+    K: Hash + Eq + Encode,
+    V: Clone + Encode,
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(CACHE_MAGIC)?;
+        (self.capacity as u64).encode(writer)?;
+        (self.len() as u64).encode(writer)?;
+        // Old entries first, so they are the first to be evicted after loading as well.
+        for (key, value) in self.old.iter().chain(&self.young) {
+            key.encode(writer)?;
+            value.encode(writer)?;
+        }
+        Ok(())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; CACHE_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *CACHE_MAGIC {
+            return Err(invalid_data("Not a BoundedCache file."));
+        }
+        let capacity = u64::decode(reader)?;
+        if capacity < 2 {
+            return Err(invalid_data("The capacity must be at least 2."));
+        }
+        let mut cache = Self::new(capacity.try_into().unwrap_or(usize::MAX));
+        for _ in 0..u64::decode(reader)? {
+            let key = K::decode(reader)?;
+            let value = V::decode(reader)?;
+            cache.insert(key, value);
+        }
+        Ok(cache)
+    }
+}
+
+const CACHE_MAGIC: &[u8; 8] = b"RFCACHE1";
+
+/// The error Encode::decode returns for bytes that don't encode a value.
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A fixed binary encoding, for saving cache keys and values.
+pub trait Encode: Sized {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads a value written by encode, returning an InvalidData error if it isn't valid.
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+impl Encode for u8 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[*self])
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0; 1];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes[0])
+    }
+}
+
+impl Encode for i8 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(u8::decode(reader)? as i8)
+    }
+}
+
+impl Encode for u16 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0; 2];
+        reader.read_exact(&mut bytes)?;
+        Ok(u16::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for u64 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+impl<A, B> Encode for (A, B)
+where
+    A: Encode,
+    B: Encode,
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+/// The length as a u64, then the elements.
+impl<T> Encode for Vec<T>
+where
+    T: Encode,
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).encode(writer)?;
+        for element in self {
+            element.encode(writer)?;
+        }
+        Ok(())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        (0..u64::decode(reader)?)
+            .map(|_| T::decode(reader))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cache::BoundedCache,
+        evaluator::{Evaluator, MinimaxEvaluator},
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+    };
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = BoundedCache::new(4);
+        for key in 0..4u64 {
+            cache.insert(key, key * 10);
+        }
+        assert_eq!(cache.len(), 4);
+        // Reading 0 keeps it, while 1 is never used again.
+        assert_eq!(cache.get(&0), Some(0));
+        cache.insert(4, 40);
+        assert!(cache.len() <= 4);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&4), Some(40));
+        assert!(cache.evictions() > 0);
+        for key in 0..1000 {
+            cache.insert(key, key);
+            assert!(cache.len() <= 4);
+        }
+    }
+
+    #[test]
+    fn bounded_minimax_is_still_perfect() {
+        let root = TicTacToe::new(Piece::X);
+        let mut unbounded = MinimaxEvaluator::new();
+        let mut bounded = MinimaxEvaluator::with_capacity(64);
+        for action in &ALL_ACTIONS {
+            assert_eq!(
+                bounded.evaluate(&root, action),
+                unbounded.evaluate(&root, action)
+            );
+        }
+        assert!(bounded.cache_len() <= 64);
+        assert!(unbounded.cache_len() > 64);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let root = TicTacToe::new(Piece::X);
+        let mut evaluator = MinimaxEvaluator::new();
+        let evaluations: Vec<i8> = ALL_ACTIONS
+            .iter()
+            .map(|action| evaluator.evaluate(&root, action))
+            .collect();
+        let path = std::env::temp_dir().join(format!("reinfors_cache_{}.bin", std::process::id()));
+        evaluator.save(&path).unwrap();
+        let mut loaded = MinimaxEvaluator::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.cache_len(), evaluator.cache_len());
+        let reloaded: Vec<i8> = ALL_ACTIONS
+            .iter()
+            .map(|action| loaded.evaluate(&root, action))
+            .collect();
+        assert_eq!(reloaded, evaluations);
+        // Everything was answered from the loaded cache.
+        assert_eq!(
+            Evaluator::<TicTacToe>::take_stats(&mut loaded).cache_misses,
+            0
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::{
+    cfr::TabularPolicy,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, ExtensiveForm},
+};
+
+/// Returns the expected payoff of Player 0 when Player 0 plays policy0 and Player 1 plays
+/// policy1.
+pub fn expected_value<G>(
+    root: &G,
+    policy0: &TabularPolicy<G::PlayerView>,
+    policy1: &TabularPolicy<G::PlayerView>,
+) -> f64
+where
+    G: ExtensiveForm,
+{
+    let actions: Vec<G::Action> = root.legal_actions().cloned().collect();
+    let probabilities = if root.is_chance() {
+        actions
+            .iter()
+            .map(|action| root.chance_probability(action))
+            .collect()
+    } else {
+        let player = root.current_player();
+        let policy = if player.index() == 0 {
+            policy0
+        } else {
+            policy1
+        };
+        policy.probabilities(&root.view_as(&player), actions.len())
+    };
+    actions
+        .iter()
+        .zip(probabilities)
+        .map(|(action, p)| {
+            p * match root.apply(action) {
+                Finished(_, outcome) => outcome.payoff(&TwoPlayer::default()),
+                Ongoing(next_state) => expected_value(&next_state, policy0, policy1),
+            }
+        })
+        .sum()
+}
+
+/// Returns the expected payoff of the responder when they play a best response against the
+/// other player using the given policy.
+pub fn best_response_value<G>(
+    root: &G,
+    policy: &TabularPolicy<G::PlayerView>,
+    responder: &TwoPlayer,
+) -> f64
+where
+    G: ExtensiveForm,
+{
+    let mut best_response = BestResponse {
+        policy,
+        responder: *responder,
+        info_sets: HashMap::new(),
+        best_actions: HashMap::new(),
+    };
+    best_response.collect(root, 1.0);
+    best_response.value(root)
+}
+
+/// NashConv: the total amount the players could gain by deviating to a best response. This is 0
+/// exactly at a Nash equilibrium. Since the game is zero sum, the values of the policy itself
+/// cancel out.
+pub fn nash_conv<G>(root: &G, policy: &TabularPolicy<G::PlayerView>) -> f64
+where
+    G: ExtensiveForm,
+{
+    [TwoPlayer::new(true), TwoPlayer::new(false)]
+        .iter()
+        .map(|player| best_response_value(root, policy, player))
+        .sum()
+}
+
+/// The average amount a best responder gains against the policy, i.e. half of the NashConv.
+pub fn exploitability<G>(root: &G, policy: &TabularPolicy<G::PlayerView>) -> f64
+where
+    G: ExtensiveForm,
+{
+    nash_conv(root, policy) / 2.0
+}
+
+/// The responder can't see the hidden information, so they must pick one action for all of the
+/// states in an information set: the one with the best value weighted by how likely chance and
+/// the opponent are to reach each state.
+struct BestResponse<'a, G>
+where
+    G: ExtensiveForm,
+{
+    policy: &'a TabularPolicy<G::PlayerView>,
+    responder: TwoPlayer,
+    /// The states of every information set of the responder, with the probability that chance
+    /// and the opponent play to them.
+    info_sets: HashMap<G::PlayerView, Vec<(G, f64)>>,
+    best_actions: HashMap<G::PlayerView, usize>,
+}
+
+impl<G> BestResponse<'_, G>
+where
+    G: ExtensiveForm,
+{
+    /// Returns the probabilities of the actions at a state that isn't the responder's.
+    fn probabilities(&self, state: &G, actions: &[G::Action]) -> Vec<f64> {
+        if state.is_chance() {
+            actions
+                .iter()
+                .map(|action| state.chance_probability(action))
+                .collect()
+        } else {
+            let info_set = state.view_as(&state.current_player());
+            self.policy.probabilities(&info_set, actions.len())
+        }
+    }
+
+    fn collect(&mut self, state: &G, reach: f64) {
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        let probabilities = if !state.is_chance() && state.current_player() == self.responder {
+            let info_set = state.view_as(&self.responder);
+            self.info_sets
+                .entry(info_set)
+                .or_default()
+                .push((state.clone(), reach));
+            vec![1.0; actions.len()]
+        } else {
+            self.probabilities(state, &actions)
+        };
+        for (action, p) in actions.iter().zip(probabilities) {
+            if let Ongoing(next_state) = state.apply(action) {
+                self.collect(&next_state, reach * p);
+            }
+        }
+    }
+
+    fn value(&mut self, state: &G) -> f64 {
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        if !state.is_chance() && state.current_player() == self.responder {
+            let best = self.best_action(state);
+            return self.child_value(state, &actions[best]);
+        }
+        self.probabilities(state, &actions)
+            .into_iter()
+            .zip(&actions)
+            .map(|(p, action)| p * self.child_value(state, action))
+            .sum()
+    }
+
+    fn child_value(&mut self, state: &G, action: &G::Action) -> f64 {
+        match state.apply(action) {
+            Finished(_, outcome) => outcome.payoff(&self.responder),
+            Ongoing(next_state) => self.value(&next_state),
+        }
+    }
+
+    fn best_action(&mut self, state: &G) -> usize {
+        let info_set = state.view_as(&self.responder);
+        if let Some(&best) = self.best_actions.get(&info_set) {
+            return best;
+        }
+        let states = self.info_sets[&info_set].clone();
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        let mut best = (0, f64::NEG_INFINITY);
+        for (i, action) in actions.iter().enumerate() {
+            let value: f64 = states
+                .iter()
+                .map(|(state, reach)| reach * self.child_value(state, action))
+                .sum();
+            if value > best.1 {
+                best = (i, value);
+            }
+        }
+        self.best_actions.insert(info_set, best.0);
+        best.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        cfr::{exploitability::exploitability, TabularPolicy},
+        games::kuhn_poker::KuhnPoker,
+    };
+
+    #[test]
+    fn uniform_policy_in_kuhn() {
+        let uniform = TabularPolicy {
+            probabilities: HashMap::new(),
+        };
+        let exploitability = exploitability(&KuhnPoker::new(), &uniform);
+        assert!((exploitability - 0.458333).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,469 @@
+pub mod exploitability;
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    game_state::{
+        outcome::Payoff, player::TwoPlayer, ApplyResult::*, ExtensiveForm, PartialInformation,
+    },
+    policy::Policy,
+    rng::Rng,
+};
+
+/// The flavours of counterfactual regret minimization implemented by CfrSolver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CfrVariant {
+    /// Walks the whole game tree every iteration.
+    Vanilla,
+    /// Like Vanilla, but regrets are floored at zero after every iteration and later iterations
+    /// weigh linearly more in the average strategy. Converges much faster in practice.
+    Plus,
+    /// Monte Carlo CFR sampling the actions of chance and the opponent, and walking every action
+    /// of the player being updated.
+    ExternalSampling,
+    /// Monte Carlo CFR sampling a single trajectory per iteration. The player being updated
+    /// explores uniformly at random with the given probability.
+    OutcomeSampling { exploration: f64 },
+}
+
+/// A behaviour strategy stored as a table from information sets to action probabilities. The
+/// probabilities are in the order of legal_actions() at any state in the information set.
+/// Information sets missing from the table are played uniformly at random.
+#[derive(Debug, Clone)]
+pub struct TabularPolicy<K> {
+    pub probabilities: HashMap<K, Vec<f64>>,
+}
+
+impl<K> TabularPolicy<K>
+where
+    K: std::hash::Hash + Eq,
+{
+    /// Returns the action probabilities at the given information set, which has n_actions legal
+    /// actions.
+    pub fn probabilities(&self, info_set: &K, n_actions: usize) -> Vec<f64> {
+        match self.probabilities.get(info_set) {
+            Some(probabilities) => probabilities.clone(),
+            None => vec![1.0 / n_actions as f64; n_actions],
+        }
+    }
+}
+
+/// Looks up the information set of the current player, so that states the player can't tell
+/// apart are played the same way.
+impl<G> Policy<G> for TabularPolicy<G::PlayerView>
+where
+    G: PartialInformation,
+    G::PlayerView: Hash + Eq,
+{
+    fn distribution(&mut self, state: &G) -> Vec<f64> {
+        let info_set = state.view_as(&state.current_player());
+        self.probabilities(&info_set, state.legal_actions().count())
+    }
+}
+
+/// The regrets and the cumulative strategy of one information set.
+#[derive(Debug, Clone)]
+struct InfoSetNode {
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>,
+    /// Regrets accumulated during a full tree walk. An information set is reached through many
+    /// states, and all of them must see the same strategy, so these are only added to the
+    /// regrets once the walk is over.
+    pending_regrets: Vec<f64>,
+}
+
+impl InfoSetNode {
+    fn new(n_actions: usize) -> Self {
+        Self {
+            regrets: vec![0.0; n_actions],
+            strategy_sum: vec![0.0; n_actions],
+            pending_regrets: vec![0.0; n_actions],
+        }
+    }
+
+    /// Adds the pending regrets to the regrets, flooring them at zero for CFR+.
+    fn commit(&mut self, floor: bool) {
+        for (regret, pending) in self.regrets.iter_mut().zip(&mut self.pending_regrets) {
+            *regret += *pending;
+            if floor {
+                *regret = regret.max(0.0);
+            }
+            *pending = 0.0;
+        }
+    }
+
+    /// Regret matching: play actions in proportion to their positive regret, or uniformly at
+    /// random if no action has positive regret.
+    fn current_strategy(&self) -> Vec<f64> {
+        let total: f64 = self.regrets.iter().map(|regret| regret.max(0.0)).sum();
+        if total > 0.0 {
+            self.regrets
+                .iter()
+                .map(|regret| regret.max(0.0) / total)
+                .collect()
+        } else {
+            vec![1.0 / self.regrets.len() as f64; self.regrets.len()]
+        }
+    }
+
+    fn average_strategy(&self) -> Vec<f64> {
+        let total: f64 = self.strategy_sum.iter().sum();
+        if total > 0.0 {
+            self.strategy_sum.iter().map(|sum| sum / total).collect()
+        } else {
+            vec![1.0 / self.strategy_sum.len() as f64; self.strategy_sum.len()]
+        }
+    }
+}
+
+/// Finds approximate Nash equilibria of two player zero sum games in extensive form by
+/// counterfactual regret minimization. The average strategy converges to an equilibrium; the
+/// strategy of the last iteration in general does not.
+///
+/// The players are updated alternately, one after the other in every iteration.
+#[derive(Debug)]
+pub struct CfrSolver<G>
+where
+    G: ExtensiveForm,
+{
+    root: G,
+    variant: CfrVariant,
+    nodes: HashMap<G::PlayerView, InfoSetNode>,
+    iterations: usize,
+    rng: Rng,
+}
+
+impl<G> CfrSolver<G>
+where
+    G: ExtensiveForm,
+{
+    /// The seed is only used by the sampling variants.
+    pub fn new(root: G, variant: CfrVariant, seed: u64) -> Self {
+        Self {
+            root,
+            variant,
+            nodes: HashMap::new(),
+            iterations: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Runs the given number of iterations.
+    pub fn run(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            self.iterate();
+        }
+    }
+
+    /// Runs one iteration, updating both players once.
+    pub fn iterate(&mut self) {
+        self.iterations += 1;
+        let root = self.root.clone();
+        for traverser in [TwoPlayer::new(true), TwoPlayer::new(false)] {
+            match self.variant {
+                CfrVariant::Vanilla | CfrVariant::Plus => {
+                    self.traverse(&root, &traverser, 1.0, 1.0);
+                    let floor = self.variant == CfrVariant::Plus;
+                    self.nodes.values_mut().for_each(|node| node.commit(floor));
+                }
+                CfrVariant::ExternalSampling => {
+                    self.external_sampling(&root, &traverser);
+                }
+                CfrVariant::OutcomeSampling { exploration } => {
+                    self.outcome_sampling(&root, &traverser, exploration, 1.0, 1.0, 1.0);
+                }
+            }
+        }
+    }
+
+    /// The average strategy of all iterations so far.
+    pub fn average_policy(&self) -> TabularPolicy<G::PlayerView> {
+        TabularPolicy {
+            probabilities: self
+                .nodes
+                .iter()
+                .map(|(info_set, node)| (info_set.clone(), node.average_strategy()))
+                .collect(),
+        }
+    }
+
+    /// The strategy that regret matching would play in the next iteration.
+    pub fn current_policy(&self) -> TabularPolicy<G::PlayerView> {
+        TabularPolicy {
+            probabilities: self
+                .nodes
+                .iter()
+                .map(|(info_set, node)| (info_set.clone(), node.current_strategy()))
+                .collect(),
+        }
+    }
+
+    fn node(&mut self, state: &G, n_actions: usize) -> &mut InfoSetNode {
+        let info_set = state.view_as(&state.current_player());
+        self.nodes
+            .entry(info_set)
+            .or_insert_with(|| InfoSetNode::new(n_actions))
+    }
+
+    /// Returns the value of the given state for the traverser, walking the full tree below it.
+    /// my_reach is the probability that the traverser plays to this state, and other_reach the
+    /// probability that chance and the opponent do.
+    fn traverse(
+        &mut self,
+        state: &G,
+        traverser: &TwoPlayer,
+        my_reach: f64,
+        other_reach: f64,
+    ) -> f64 {
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        if state.is_chance() {
+            return actions
+                .iter()
+                .map(|action| {
+                    let probability = state.chance_probability(action);
+                    probability
+                        * self.child_value(
+                            state,
+                            action,
+                            traverser,
+                            my_reach,
+                            other_reach * probability,
+                        )
+                })
+                .sum();
+        }
+        let strategy = self.node(state, actions.len()).current_strategy();
+        if state.current_player() != *traverser {
+            return actions
+                .iter()
+                .zip(&strategy)
+                .map(|(action, &p)| {
+                    p * self.child_value(state, action, traverser, my_reach, other_reach * p)
+                })
+                .sum();
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .zip(&strategy)
+            .map(|(action, &p)| {
+                self.child_value(state, action, traverser, my_reach * p, other_reach)
+            })
+            .collect();
+        let value: f64 = values.iter().zip(&strategy).map(|(v, p)| v * p).sum();
+        let weight = match self.variant {
+            CfrVariant::Plus => self.iterations as f64,
+            _ => 1.0,
+        };
+        let node = self.node(state, actions.len());
+        for i in 0..actions.len() {
+            node.pending_regrets[i] += other_reach * (values[i] - value);
+            node.strategy_sum[i] += weight * my_reach * strategy[i];
+        }
+        value
+    }
+
+    fn child_value(
+        &mut self,
+        state: &G,
+        action: &G::Action,
+        traverser: &TwoPlayer,
+        my_reach: f64,
+        other_reach: f64,
+    ) -> f64 {
+        match state.apply(action) {
+            Finished(_, outcome) => outcome.payoff(traverser),
+            Ongoing(next_state) => self.traverse(&next_state, traverser, my_reach, other_reach),
+        }
+    }
+
+    /// External sampling: chance and the opponent sample one action, the traverser tries them
+    /// all. The opponent's average strategy is updated at the nodes it samples from.
+    fn external_sampling(&mut self, state: &G, traverser: &TwoPlayer) -> f64 {
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        if state.is_chance() {
+            let weights: Vec<f64> = actions
+                .iter()
+                .map(|a| state.chance_probability(a))
+                .collect();
+            let action = &actions[self.rng.gen_weighted_index(&weights)];
+            return self.external_child(state, action, traverser);
+        }
+        let strategy = self.node(state, actions.len()).current_strategy();
+        if state.current_player() != *traverser {
+            let node = self.node(state, actions.len());
+            node.strategy_sum
+                .iter_mut()
+                .zip(&strategy)
+                .for_each(|(sum, p)| *sum += p);
+            let action = &actions[self.rng.gen_weighted_index(&strategy)];
+            return self.external_child(state, action, traverser);
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .map(|action| self.external_child(state, action, traverser))
+            .collect();
+        let value: f64 = values.iter().zip(&strategy).map(|(v, p)| v * p).sum();
+        let node = self.node(state, actions.len());
+        node.regrets
+            .iter_mut()
+            .zip(&values)
+            .for_each(|(regret, v)| *regret += v - value);
+        value
+    }
+
+    fn external_child(&mut self, state: &G, action: &G::Action, traverser: &TwoPlayer) -> f64 {
+        match state.apply(action) {
+            Finished(_, outcome) => outcome.payoff(traverser),
+            Ongoing(next_state) => self.external_sampling(&next_state, traverser),
+        }
+    }
+
+    /// Outcome sampling: follow a single sampled trajectory. Returns the sampled utility of the
+    /// traverser divided by the probability of sampling the trajectory, and the probability of
+    /// playing from this state to the end of the trajectory under the current strategies.
+    fn outcome_sampling(
+        &mut self,
+        state: &G,
+        traverser: &TwoPlayer,
+        exploration: f64,
+        my_reach: f64,
+        other_reach: f64,
+        sample_reach: f64,
+    ) -> (f64, f64) {
+        let actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        if state.is_chance() {
+            let weights: Vec<f64> = actions
+                .iter()
+                .map(|a| state.chance_probability(a))
+                .collect();
+            let i = self.rng.gen_weighted_index(&weights);
+            let (utility, tail) = self.outcome_child(
+                state,
+                &actions[i],
+                traverser,
+                exploration,
+                my_reach,
+                other_reach * weights[i],
+                sample_reach * weights[i],
+            );
+            return (utility, tail * weights[i]);
+        }
+        let n_actions = actions.len();
+        let strategy = self.node(state, n_actions).current_strategy();
+        let is_traverser = state.current_player() == *traverser;
+        let sampling: Vec<f64> = if is_traverser {
+            strategy
+                .iter()
+                .map(|p| exploration / n_actions as f64 + (1.0 - exploration) * p)
+                .collect()
+        } else {
+            strategy.clone()
+        };
+        let i = self.rng.gen_weighted_index(&sampling);
+        let (my_next, other_next) = if is_traverser {
+            (my_reach * strategy[i], other_reach)
+        } else {
+            (my_reach, other_reach * strategy[i])
+        };
+        let (utility, tail) = self.outcome_child(
+            state,
+            &actions[i],
+            traverser,
+            exploration,
+            my_next,
+            other_next,
+            sample_reach * sampling[i],
+        );
+        if is_traverser {
+            let weight = utility * other_reach;
+            let node = self.node(state, n_actions);
+            for (j, regret) in node.regrets.iter_mut().enumerate() {
+                *regret += if j == i {
+                    weight * tail * (1.0 - strategy[i])
+                } else {
+                    -weight * tail * strategy[i]
+                };
+            }
+            for (sum, p) in node.strategy_sum.iter_mut().zip(&strategy) {
+                *sum += my_reach * p / sample_reach;
+            }
+        }
+        (utility, tail * strategy[i])
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn outcome_child(
+        &mut self,
+        state: &G,
+        action: &G::Action,
+        traverser: &TwoPlayer,
+        exploration: f64,
+        my_reach: f64,
+        other_reach: f64,
+        sample_reach: f64,
+    ) -> (f64, f64) {
+        match state.apply(action) {
+            Finished(_, outcome) => (outcome.payoff(traverser) / sample_reach, 1.0),
+            Ongoing(next_state) => self.outcome_sampling(
+                &next_state,
+                traverser,
+                exploration,
+                my_reach,
+                other_reach,
+                sample_reach,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cfr::{exploitability, CfrSolver, CfrVariant},
+        games::kuhn_poker::KuhnPoker,
+    };
+
+    const GAME_VALUE: f64 = -1.0 / 18.0;
+
+    fn assert_solves(variant: CfrVariant, iterations: usize, tolerance: f64) {
+        let mut solver = CfrSolver::new(KuhnPoker::new(), variant, 0);
+        solver.run(iterations);
+        let policy = solver.average_policy();
+        let value = exploitability::expected_value(&KuhnPoker::new(), &policy, &policy);
+        let exploitability = exploitability::exploitability(&KuhnPoker::new(), &policy);
+        assert!((value - GAME_VALUE).abs() < tolerance, "value {}", value);
+        assert!(
+            exploitability < tolerance,
+            "exploitability {}",
+            exploitability
+        );
+    }
+
+    #[test]
+    fn vanilla_solves_kuhn() {
+        assert_solves(CfrVariant::Vanilla, 1000, 0.005);
+    }
+
+    #[test]
+    fn plus_solves_kuhn() {
+        assert_solves(CfrVariant::Plus, 300, 0.001);
+    }
+
+    #[test]
+    fn external_sampling_solves_kuhn() {
+        assert_solves(CfrVariant::ExternalSampling, 20000, 0.02);
+    }
+
+    #[test]
+    fn outcome_sampling_solves_kuhn() {
+        assert_solves(
+            CfrVariant::OutcomeSampling { exploration: 0.6 },
+            100000,
+            0.05,
+        );
+    }
+}
@@ -0,0 +1,223 @@
+pub mod vector;
+
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, GameState},
+    strategy::Strategy,
+};
+
+/// A single agent environment in the style of Gym: reset starts an episode, and step takes the
+/// agent's action and returns what it observes next, its reward and whether the episode is over.
+pub trait Environment {
+    type Observation;
+
+    type Action;
+
+    /// Starts a new episode and returns the first observation, the reward received before the
+    /// agent could act and whether the episode is already over, in which case it must be reset
+    /// again before stepping.
+    fn reset(&mut self) -> (Self::Observation, f64, bool);
+
+    /// Takes action, which must be legal, and returns the next observation, the reward and
+    /// whether the episode is over. Panics if the episode is already over.
+    fn step(&mut self, action: &Self::Action) -> (Self::Observation, f64, bool);
+
+    /// The actions the agent may take now.
+    fn legal_actions(&self) -> Vec<Self::Action>;
+}
+
+/// Turns a two player game into an Environment for one seat, the learner. The other seat is
+/// played by a fixed opponent, which chooses its actions with the given strategy and evaluator and
+/// moves automatically, so observations are always states where the learner is to move.
+///
+/// The reward is 0 until the game ends, and then the learner's payoff, which for WinDraw is 1 for
+/// a win, 0 for a draw and -1 for a loss. The observation at the end of the game is the final
+/// state.
+#[derive(Debug)]
+pub struct GameEnvironment<G, E, S> {
+    root: G,
+    state: G,
+    learner: TwoPlayer,
+    evaluator: E,
+    strategy: S,
+    done: bool,
+}
+
+impl<G, E, S> GameEnvironment<G, E, S>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Clone,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    S: Strategy<G, E>,
+{
+    /// Every episode starts from root. Call reset before the first step.
+    pub fn new(root: G, learner: TwoPlayer, evaluator: E, strategy: S) -> Self {
+        Self {
+            state: root.clone(),
+            root,
+            learner,
+            evaluator,
+            strategy,
+            done: true,
+        }
+    }
+
+    pub fn state(&self) -> &G {
+        &self.state
+    }
+
+    pub fn learner(&self) -> TwoPlayer {
+        self.learner
+    }
+
+    /// Changes the learner's seat from the next reset on.
+    pub fn set_learner(&mut self, learner: TwoPlayer) {
+        self.learner = learner;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Lets the opponent move until it is the learner's turn or the game ends. Returns the
+    /// learner's reward.
+    fn opponent_moves(&mut self) -> f64 {
+        while self.state.current_player() != self.learner {
+            let action = self.strategy.choose(&self.state, &mut self.evaluator);
+            match self.state.apply(&action) {
+                Ongoing(next_state) => self.state = next_state,
+                Finished(final_state, outcome) => {
+                    self.state = final_state;
+                    self.done = true;
+                    return outcome.payoff(&self.learner);
+                }
+            }
+        }
+        0.0
+    }
+}
+
+impl<G, E, S> Environment for GameEnvironment<G, E, S>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Clone,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    S: Strategy<G, E>,
+{
+    type Observation = G;
+
+    type Action = G::Action;
+
+    /// If the opponent moves first and ends the game before the learner's turn, the episode
+    /// starts out done, and the reward is the learner's payoff.
+    fn reset(&mut self) -> (G, f64, bool) {
+        self.state = self.root.clone();
+        self.done = false;
+        let reward = self.opponent_moves();
+        (self.state.clone(), reward, self.done)
+    }
+
+    fn step(&mut self, action: &G::Action) -> (G, f64, bool) {
+        assert!(
+            !self.done,
+            "The episode is over. Call reset to start a new one."
+        );
+        let reward = match self.state.apply(action) {
+            Finished(final_state, outcome) => {
+                self.state = final_state;
+                self.done = true;
+                outcome.payoff(&self.learner)
+            }
+            Ongoing(next_state) => {
+                self.state = next_state;
+                self.opponent_moves()
+            }
+        };
+        (self.state.clone(), reward, self.done)
+    }
+
+    fn legal_actions(&self) -> Vec<G::Action> {
+        if self.done {
+            vec![]
+        } else {
+            self.state.legal_actions().cloned().collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        environment::{Environment, GameEnvironment},
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_state::{player::TwoPlayer, GameState},
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        rng::Rng,
+        strategy::GreedyStrategy,
+    };
+
+    #[test]
+    fn learner_always_moves() {
+        let root = TicTacToe::new(Piece::X);
+        let mut rng = Rng::new(8);
+        for seat in [TwoPlayer::new(true), TwoPlayer::new(false)] {
+            let mut env = GameEnvironment::new(root, seat, RandomEvaluator::new(5), GreedyStrategy);
+            for _ in 0..20 {
+                let (mut observation, reward, done) = env.reset();
+                assert_eq!((reward, done), (0.0, false));
+                loop {
+                    assert_eq!(observation.current_player(), seat);
+                    let actions = env.legal_actions();
+                    let action = *rng.choose(&actions).unwrap();
+                    let (next, reward, done) = env.step(&action);
+                    if done {
+                        assert!([-1.0, 0.0, 1.0].contains(&reward));
+                        assert!(env.legal_actions().is_empty());
+                        break;
+                    }
+                    assert_eq!(reward, 0.0);
+                    observation = next;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn minimax_opponent_punishes_bad_play() {
+        let root = TicTacToe::new(Piece::X);
+        let mut env = GameEnvironment::new(
+            root,
+            TwoPlayer::new(false),
+            MinimaxEvaluator::new(),
+            GreedyStrategy,
+        );
+        env.reset();
+        let reward = loop {
+            let action = env.legal_actions()[0];
+            let (_, reward, done) = env.step(&action);
+            if done {
+                break reward;
+            }
+        };
+        assert_eq!(reward, -1.0);
+    }
+
+    #[test]
+    fn opponent_can_end_the_episode_during_reset() {
+        // X to move can win at once, and the learner plays O.
+        let mut root = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            root.apply_mut(&ALL_ACTIONS[action]);
+        }
+        let mut env = GameEnvironment::new(
+            root,
+            TwoPlayer::new(false),
+            MinimaxEvaluator::new(),
+            GreedyStrategy,
+        );
+        let (observation, reward, done) = env.reset();
+        assert_eq!((reward, done), (-1.0, true));
+        assert!(env.is_done() && env.legal_actions().is_empty());
+        assert_ne!(observation, root);
+    }
+}
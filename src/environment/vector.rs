@@ -0,0 +1,296 @@
+use std::thread;
+
+use crate::{environment::Environment, game_state::Features};
+
+/// The results of stepping every environment of a VecEnvironment once. Row i of observations
+/// holds the features of environment i, and an environment whose episode ended has already been
+/// reset, so its row is the first observation of the next episode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VecStep<'a> {
+    /// observation_size values for each environment, one after the other.
+    pub observations: &'a [f64],
+    pub rewards: &'a [f64],
+    pub dones: &'a [bool],
+    /// Laid out like observations. Row i holds the last observation of the episode that ended if
+    /// dones[i], and zeros otherwise.
+    pub final_observations: &'a [f64],
+}
+
+/// The number of times in a row an environment may end its episode during reset before
+/// VecEnvironment gives up on it.
+const MAX_RESETS: usize = 1000;
+
+/// Holds N copies of an environment and steps them in lockstep with one action each, writing the
+/// Features of their observations into one contiguous buffer, which is what batched learners
+/// consume.
+///
+/// With more than one worker, the environments are split into contiguous chunks that are stepped
+/// on scoped threads. Every environment is stepped exactly as it would be on one thread, so the
+/// results don't depend on the number of workers.
+#[derive(Debug)]
+pub struct VecEnvironment<E> {
+    envs: Vec<E>,
+    workers: usize,
+    observations: Vec<f64>,
+    rewards: Vec<f64>,
+    dones: Vec<bool>,
+    final_observations: Vec<f64>,
+}
+
+impl<E> VecEnvironment<E>
+where
+    E: Environment<Observation: Features> + Send,
+    E::Action: Sync,
+{
+    /// Steps every environment on the current thread.
+    pub fn new(envs: Vec<E>) -> Self {
+        Self::with_workers(envs, 1)
+    }
+
+    pub fn with_workers(envs: Vec<E>, workers: usize) -> Self {
+        assert!(!envs.is_empty(), "There must be at least one environment.");
+        assert!(workers > 0, "There must be at least one worker.");
+        let n = envs.len();
+        Self {
+            envs,
+            workers,
+            observations: vec![0.0; n * Self::observation_size()],
+            rewards: vec![0.0; n],
+            dones: vec![false; n],
+            final_observations: vec![0.0; n * Self::observation_size()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    /// The number of values in the observation of one environment.
+    pub fn observation_size() -> usize {
+        E::Observation::N_FEATURES
+    }
+
+    pub fn envs(&self) -> &[E] {
+        &self.envs
+    }
+
+    /// The legal actions of every environment.
+    pub fn legal_actions(&self) -> Vec<Vec<E::Action>> {
+        self.envs.iter().map(|env| env.legal_actions()).collect()
+    }
+
+    /// Resets every environment and returns their observations. Episodes that end during reset,
+    /// before the agent gets to act, are skipped.
+    pub fn reset(&mut self) -> &[f64] {
+        self.run(None);
+        &self.observations
+    }
+
+    /// Takes actions[i] in environment i, resetting the ones whose episode ends like reset does.
+    pub fn step(&mut self, actions: &[E::Action]) -> VecStep<'_> {
+        assert_eq!(
+            actions.len(),
+            self.len(),
+            "Expected one action per environment."
+        );
+        self.run(Some(actions));
+        VecStep {
+            observations: &self.observations,
+            rewards: &self.rewards,
+            dones: &self.dones,
+            final_observations: &self.final_observations,
+        }
+    }
+
+    /// Resets every environment if actions is None and steps them otherwise, splitting them
+    /// among the workers.
+    fn run(&mut self, actions: Option<&[E::Action]>) {
+        let size = Self::observation_size();
+        let chunk = self.len().div_ceil(self.workers);
+        if self.workers == 1 {
+            run_chunk(
+                &mut self.envs,
+                actions,
+                &mut self.observations,
+                &mut self.rewards,
+                &mut self.dones,
+                &mut self.final_observations,
+            );
+            return;
+        }
+        thread::scope(|scope| {
+            let chunks = self
+                .envs
+                .chunks_mut(chunk)
+                .zip(self.observations.chunks_mut(chunk * size))
+                .zip(self.rewards.chunks_mut(chunk))
+                .zip(self.dones.chunks_mut(chunk))
+                .zip(self.final_observations.chunks_mut(chunk * size))
+                .enumerate();
+            for (i, ((((envs, observations), rewards), dones), final_observations)) in chunks {
+                let actions = actions.map(|actions| &actions[i * chunk..i * chunk + envs.len()]);
+                scope.spawn(move || {
+                    run_chunk(
+                        envs,
+                        actions,
+                        observations,
+                        rewards,
+                        dones,
+                        final_observations,
+                    )
+                });
+            }
+        });
+    }
+}
+
+fn run_chunk<E>(
+    envs: &mut [E],
+    actions: Option<&[E::Action]>,
+    observations: &mut [f64],
+    rewards: &mut [f64],
+    dones: &mut [bool],
+    final_observations: &mut [f64],
+) where
+    E: Environment<Observation: Features>,
+{
+    let size = E::Observation::N_FEATURES;
+    for (i, env) in envs.iter_mut().enumerate() {
+        let row = i * size..(i + 1) * size;
+        final_observations[row.clone()].fill(0.0);
+        let observation = match actions {
+            None => {
+                rewards[i] = 0.0;
+                dones[i] = false;
+                reset(env)
+            }
+            Some(actions) => {
+                let (observation, reward, done) = env.step(&actions[i]);
+                rewards[i] = reward;
+                dones[i] = done;
+                if done {
+                    final_observations[row.clone()].copy_from_slice(&observation.features());
+                    reset(env)
+                } else {
+                    observation
+                }
+            }
+        };
+        observations[row].copy_from_slice(&observation.features());
+    }
+}
+
+/// Resets env until it starts an episode in which the agent gets to act, and returns its first
+/// observation.
+fn reset<E>(env: &mut E) -> E::Observation
+where
+    E: Environment,
+{
+    for _ in 0..MAX_RESETS {
+        let (observation, _, done) = env.reset();
+        if !done {
+            return observation;
+        }
+    }
+    panic!("The episode ended during reset {MAX_RESETS} times in a row.");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        environment::{vector::VecEnvironment, GameEnvironment},
+        evaluator::RandomEvaluator,
+        game_state::player::TwoPlayer,
+        game_state::Features,
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        rng::Rng,
+        strategy::GreedyStrategy,
+    };
+
+    type TicTacToeEnv = GameEnvironment<TicTacToe, RandomEvaluator, GreedyStrategy>;
+
+    fn envs(n: usize) -> Vec<TicTacToeEnv> {
+        (0..n)
+            .map(|i| {
+                let learner = TwoPlayer::new(i.is_multiple_of(2));
+                let opponent = RandomEvaluator::new(i as u64);
+                GameEnvironment::new(TicTacToe::new(Piece::X), learner, opponent, GreedyStrategy)
+            })
+            .collect()
+    }
+
+    /// Plays random actions and returns every observation, reward and done flag.
+    fn rollout(workers: usize) -> (Vec<f64>, Vec<f64>, Vec<bool>) {
+        let mut vec_env = VecEnvironment::with_workers(envs(7), workers);
+        let mut rng = Rng::new(4);
+        let mut observations = vec_env.reset().to_vec();
+        let (mut rewards, mut dones) = (vec![], vec![]);
+        for _ in 0..30 {
+            let actions: Vec<_> = vec_env
+                .legal_actions()
+                .iter()
+                .map(|legal| *rng.choose(legal).unwrap())
+                .collect();
+            let step = vec_env.step(&actions);
+            assert_eq!(step.observations.len(), 7 * TicTacToe::N_FEATURES);
+            observations.extend(step.observations);
+            rewards.extend(step.rewards);
+            dones.extend(step.dones);
+        }
+        (observations, rewards, dones)
+    }
+
+    #[test]
+    fn workers_do_not_change_results() {
+        let single = rollout(1);
+        // Many episodes ended and were reset along the way.
+        assert!(single.2.iter().filter(|&&done| done).count() > 20);
+        assert!(single
+            .1
+            .iter()
+            .zip(&single.2)
+            .all(|(reward, done)| *done || *reward == 0.0));
+        assert_eq!(rollout(3), single);
+        assert_eq!(rollout(8), single);
+    }
+
+    #[test]
+    fn skips_episodes_that_end_during_reset() {
+        // X to move can win at once, and the learners play O, so a random opponent ends about a
+        // fifth of the episodes before the learner acts.
+        let mut root = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            root.apply_mut(&ALL_ACTIONS[action]);
+        }
+        let envs = (0..6)
+            .map(|i| {
+                let opponent = RandomEvaluator::new(i);
+                GameEnvironment::new(root, TwoPlayer::new(false), opponent, GreedyStrategy)
+            })
+            .collect();
+        let mut vec_env = VecEnvironment::with_workers(envs, 2);
+        let size = TicTacToe::N_FEATURES;
+        let mut rng = Rng::new(1);
+        vec_env.reset();
+        let mut episodes = 0;
+        for _ in 0..40 {
+            assert!(vec_env.envs().iter().all(|env| !env.is_done()));
+            let actions: Vec<_> = vec_env
+                .legal_actions()
+                .iter()
+                .map(|legal| *rng.choose(legal).unwrap())
+                .collect();
+            let step = vec_env.step(&actions);
+            for (i, &done) in step.dones.iter().enumerate() {
+                let last = &step.final_observations[i * size..(i + 1) * size];
+                assert_eq!(last.iter().any(|&value| value != 0.0), done);
+                episodes += done as usize;
+            }
+        }
+        assert!(episodes > 40, "{episodes}");
+    }
+}
@@ -1,10 +1,15 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{hash::Hash, io, path::Path};
 
-use crate::game_state::{
-    outcome::WinDraw::{self, *},
-    player::TwoPlayer,
-    ApplyResult::*,
-    GameState,
+use crate::{
+    cache::{BoundedCache, Encode},
+    game_state::{
+        outcome::WinDraw::{self, *},
+        player::TwoPlayer,
+        ApplyResult::*,
+        GameState,
+    },
+    rng::Rng,
+    search::{SearchStats, StatsTracker},
 };
 
 pub trait Evaluator<G>
@@ -14,6 +19,12 @@ where
     type Evaluation;
 
     fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation;
+
+    /// Returns the statistics of the work done since the last call, and starts counting again.
+    /// Evaluators that don't search report nothing.
+    fn take_stats(&mut self) -> SearchStats {
+        SearchStats::new()
+    }
 }
 
 pub trait ToEvaluation<G>: Evaluator<G>
@@ -23,14 +34,18 @@ where
     fn to_evaluation(&self, player: &G::Player, outcome: &G::Outcome) -> Self::Evaluation;
 }
 
-#[derive(Debug)]
+/// Evaluates every action with a uniformly random number, so GreedyStrategy plays uniformly at
+/// random.
+#[derive(Debug, Clone)]
 pub struct RandomEvaluator {
-    rng_state: u64,
+    rng: Rng,
 }
 
 impl RandomEvaluator {
     pub fn new(seed: u64) -> Self {
-        Self { rng_state: seed }
+        Self {
+            rng: Rng::new(seed),
+        }
     }
 }
 
@@ -41,20 +56,66 @@ where
     type Evaluation = u64;
 
     fn evaluate(&mut self, _state: &G, _action: &G::Action) -> Self::Evaluation {
-        const A: u64 = 1664525;
-        const C: u64 = 1013904223;
-
-        self.rng_state = self.rng_state.wrapping_mul(A).wrapping_add(C);
-        self.rng_state
+        self.rng.next_u64()
     }
 }
 
 /// This evaluator recurses through the legal actions available at each stage of the game and thus
 /// MAY BE VERY EXPENSIVE TO COMPUTE!!! This evaluator is completely infeasible to compute for
 /// anything more than very simple games.
+///
+/// The evaluations of the states it has seen are cached, without a limit by default. A bounded
+/// cache evicts the least recently used states, which are evaluated again if they come back.
 #[derive(Debug)]
 pub struct MinimaxEvaluator<G> {
-    visited: HashMap<G, i8>,
+    visited: BoundedCache<G, i8>,
+    tracker: StatsTracker,
+}
+
+impl<G> Default for MinimaxEvaluator<G> {
+    fn default() -> Self {
+        Self::with_cache(BoundedCache::unbounded())
+    }
+}
+
+impl<G> MinimaxEvaluator<G> {
+    fn with_cache(visited: BoundedCache<G, i8>) -> Self {
+        Self {
+            visited,
+            tracker: StatsTracker::new(),
+        }
+    }
+
+    /// An evaluator that caches at most capacity states, which must be at least 2.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_cache(BoundedCache::new(capacity))
+    }
+
+    /// The number of cached states.
+    pub fn cache_len(&self) -> usize {
+        self.visited.len()
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.visited.clear();
+    }
+
+    /// Writes the cache to the file at path, so a later run can load it instead of searching
+    /// again.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()>
+    where
+        G: Hash + Eq + Encode,
+    {
+        self.visited.save(path)
+    }
+
+    /// Reads an evaluator whose cache was written by save, with the capacity it was saved with.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self>
+    where
+        G: Hash + Eq + Encode,
+    {
+        Ok(Self::with_cache(BoundedCache::load(path)?))
+    }
 }
 
 impl<G> MinimaxEvaluator<G>
@@ -71,9 +132,7 @@ where
     }
 
     pub fn new() -> Self {
-        Self {
-            visited: HashMap::new(),
-        }
+        Default::default()
     }
 }
 
@@ -89,6 +148,23 @@ where
     /// next player and return the Evaluaton (from the perpective of the caller) of the most
     /// favorable action for the opponent.
     fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation {
+        self.tracker.enter();
+        let eval = self.search(state, action);
+        self.tracker.exit();
+        eval
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        self.tracker.take(self.visited.len())
+    }
+}
+
+impl<G> MinimaxEvaluator<G>
+where
+    G: GameState<Outcome = WinDraw<G>, Player = TwoPlayer> + Hash + Eq,
+{
+    /// The body of evaluate, which recurses through evaluate so every node is counted.
+    fn search(&mut self, state: &G, action: &G::Action) -> i8 {
         // Keep track of who called evaluate.
         let original_player = state.current_player();
         // Get new state.
@@ -97,10 +173,12 @@ where
             Finished(_, outcome) => return self.outcome_to_eval(&original_player, &outcome),
         };
         // If state already visited and evaluated, return the outcome.
-        if let Some(&eval) = self.visited.get(&new_state) {
+        if let Some(eval) = self.visited.get(&new_state) {
+            self.tracker.stats.cache_hits += 1;
             return eval;
             // Check if we're in a final state, if so cache it and return.
         };
+        self.tracker.stats.cache_misses += 1;
         // Couldn't immediately tell what the value is, so recurse.
         let mut eval = 1;
         let mut actions = new_state.legal_actions();
@@ -1,5 +1,332 @@
-use crate::{evaluator::Evaluator, game_state::*, strategy::Strategy};
-use std::fmt::{Debug, Display};
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, *},
+    learning::replay::Trajectory,
+    observer::{ConsoleObserver, Observer},
+    rng::Rng,
+    search::SearchStats,
+    strategy::Strategy,
+};
+use std::{
+    fmt::{Debug, Display},
+    thread,
+};
+
+/// Chooses the actions of one seat in a Match.
+pub trait Agent<G>
+where
+    G: GameState,
+{
+    /// Returns a legal action for the current player of state, which isn't over.
+    fn act(&mut self, state: &G) -> G::Action;
+
+    /// Returns the statistics of the searching done since the last call, as
+    /// Evaluator::take_stats does. Agents that don't search report nothing.
+    fn take_stats(&mut self) -> SearchStats {
+        SearchStats::new()
+    }
+}
+
+/// Lets a Match borrow an agent, so that it keeps its state, such as a cache, across matches.
+impl<G, A> Agent<G> for &mut A
+where
+    G: GameState,
+    A: Agent<G> + ?Sized,
+{
+    fn act(&mut self, state: &G) -> G::Action {
+        (**self).act(state)
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        (**self).take_stats()
+    }
+}
+
+/// Lets agents of different types be chosen at runtime, as ParallelArena does.
+impl<G, A> Agent<G> for Box<A>
+where
+    G: GameState,
+    A: Agent<G> + ?Sized,
+{
+    fn act(&mut self, state: &G) -> G::Action {
+        (**self).act(state)
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        (**self).take_stats()
+    }
+}
+
+/// Plays the chance nodes of an ExtensiveForm game, sampling their outcome by
+/// chance_probability, and leaves every other decision to the wrapped agent.
+///
+/// Match asks the agent of current_player to act, which is meaningless at chance nodes, so wrap
+/// both agents to play a game that starts before the deal, e.g. LeducHoldem::new().
+#[derive(Debug, Clone)]
+pub struct ChanceAgent<A> {
+    pub agent: A,
+    rng: Rng,
+}
+
+impl<A> ChanceAgent<A> {
+    pub fn new(agent: A, seed: u64) -> Self {
+        Self {
+            agent,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G, A> Agent<G> for ChanceAgent<A>
+where
+    G: ExtensiveForm,
+    A: Agent<G>,
+{
+    fn act(&mut self, state: &G) -> G::Action {
+        if !state.is_chance() {
+            return self.agent.act(state);
+        }
+        let actions: Vec<&G::Action> = state.legal_actions().collect();
+        let weights: Vec<f64> = actions
+            .iter()
+            .map(|action| state.chance_probability(action))
+            .collect();
+        actions[self.rng.gen_weighted_index(&weights)].clone()
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        self.agent.take_stats()
+    }
+}
+
+/// Plays a seat with an Evaluator and a Strategy, the way GamePlayer plays every seat.
+#[derive(Debug, Clone)]
+pub struct StrategyAgent<E, S> {
+    pub evaluator: E,
+    pub strategy: S,
+}
+
+impl<E, S> StrategyAgent<E, S> {
+    pub fn new(evaluator: E, strategy: S) -> Self {
+        Self {
+            evaluator,
+            strategy,
+        }
+    }
+}
+
+impl<G, E, S> Agent<G> for StrategyAgent<E, S>
+where
+    G: GameState,
+    E: Evaluator<G>,
+    S: Strategy<G, E>,
+{
+    fn act(&mut self, state: &G) -> G::Action {
+        self.strategy.choose(state, &mut self.evaluator)
+    }
+
+    /// The statistics of the strategy merged with those of the evaluator.
+    fn take_stats(&mut self) -> SearchStats {
+        take_stats(&mut self.evaluator, &mut self.strategy)
+    }
+}
+
+/// A person at the terminal, who enters an action on stdin. The agent only prompts for the
+/// action, so play with a ConsoleObserver, e.g. with Match::play_display, to show the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanAgent;
+
+impl<G> Agent<G> for HumanAgent
+where
+    G: Interactive,
+{
+    fn act(&mut self, state: &G) -> G::Action {
+        state.get_user_input()
+    }
+}
+
+/// Plays a two player game with a separate Agent in each seat, e.g. MinimaxEvaluator against
+/// RandomEvaluator, or a HumanAgent against either. The agent of Player 0 moves whenever Player 0
+/// is to move, whichever player the game starts with.
+pub struct Match<'a, G>
+where
+    G: GameState<Player = TwoPlayer>,
+{
+    pub state: G,
+    agents: [Box<dyn Agent<G> + 'a>; 2],
+}
+
+impl<'a, G> Match<'a, G>
+where
+    G: GameState<Player = TwoPlayer>,
+{
+    pub fn new(state: G, player0: impl Agent<G> + 'a, player1: impl Agent<G> + 'a) -> Self {
+        Self {
+            state,
+            agents: [Box::new(player0), Box::new(player1)],
+        }
+    }
+
+    pub fn state(&self) -> &G {
+        &self.state
+    }
+
+    /// The agent playing the given player.
+    pub fn agent(&mut self, player: &TwoPlayer) -> &mut (dyn Agent<G> + 'a) {
+        self.agents[player.index()].as_mut()
+    }
+
+    /// Swaps the agents, so each plays the other player from now on.
+    pub fn swap_seats(&mut self) {
+        self.agents.swap(0, 1);
+    }
+
+    pub fn play(&mut self) -> (G, G::Outcome) {
+        self.play_observed(&mut [])
+    }
+
+    /// Plays like play, printing every state.
+    pub fn play_display(&mut self) -> (G, G::Outcome)
+    where
+        G: Display,
+    {
+        self.play_observed(&mut [&mut ConsoleObserver])
+    }
+
+    /// Plays like play, invoking the observers along the way. Agents don't expose evaluations,
+    /// so before_move always gets an empty slice.
+    pub fn play_observed(&mut self, observers: &mut [&mut dyn Observer<G>]) -> (G, G::Outcome) {
+        play_loop(
+            &mut self.state,
+            observers,
+            &mut self.agents,
+            |_, _| vec![],
+            |state, _, agents| {
+                let agent = &mut agents[state.current_player().index()];
+                let action = agent.act(state);
+                (action, Some(agent.take_stats()))
+            },
+        )
+    }
+}
+
+/// The game loop behind Match and GamePlayer, which plays from state until the game ends,
+/// invoking the observers along the way.
+///
+/// players is passed to evaluations and choose. evaluations returns the evaluation of every
+/// legal action, and is only called if some observer wants evaluations. choose gets the state
+/// and the ply, and returns the action to play with the SearchStats of the search that chose it,
+/// or None if a person chose it.
+fn play_loop<G, V, P>(
+    state: &mut G,
+    observers: &mut [&mut dyn Observer<G, V>],
+    players: &mut P,
+    mut evaluations: impl FnMut(&G, &mut P) -> Vec<(G::Action, V)>,
+    mut choose: impl FnMut(&G, usize, &mut P) -> (G::Action, Option<SearchStats>),
+) -> (G, G::Outcome)
+where
+    G: GameState,
+{
+    for observer in observers.iter_mut() {
+        observer.game_start(state);
+    }
+    let mut ply = 0;
+    loop {
+        let evaluations = if observers
+            .iter()
+            .any(|observer| observer.wants_evaluations())
+        {
+            evaluations(state, players)
+        } else {
+            vec![]
+        };
+        for observer in observers.iter_mut() {
+            observer.before_move(state, &evaluations);
+        }
+        let (action, stats) = choose(state, ply, players);
+        if let Some(stats) = stats {
+            for observer in observers.iter_mut() {
+                observer.after_search(state, &stats);
+            }
+        }
+        let (next, outcome) = match state.apply(&action) {
+            Ongoing(new_state) => (new_state, None),
+            Finished(new_state, outcome) => (new_state, Some(outcome)),
+        };
+        for observer in observers.iter_mut() {
+            observer.after_move(state, &action, &next);
+        }
+        match outcome {
+            None => *state = next,
+            Some(outcome) => {
+                for observer in observers.iter_mut() {
+                    observer.game_end(&next, &outcome);
+                }
+                return (next, outcome);
+            }
+        }
+        ply += 1;
+    }
+}
+
+/// Runs play for games independent games, split into contiguous chunks that run on the given
+/// number of scoped threads, and returns the results in the order of the games.
+///
+/// play gets the index of the game and a seed for it. The seeds are drawn from an Rng seeded
+/// with seed before any game starts, so every result depends only on seed and its index, not on
+/// the number of workers. Anything play needs, such as agents, should be constructed inside it.
+pub fn run_parallel<T, F>(games: usize, workers: usize, seed: u64, play: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize, u64) -> T + Sync,
+{
+    assert!(workers > 0, "There must be at least one worker.");
+    let mut rng = Rng::new(seed);
+    let seeds: Vec<u64> = (0..games).map(|_| rng.next_u64()).collect();
+    let chunk = games.div_ceil(workers).max(1);
+    let play = &play;
+    thread::scope(|scope| {
+        let handles: Vec<_> = seeds
+            .chunks(chunk)
+            .enumerate()
+            .map(|(i, seeds)| {
+                scope.spawn(move || {
+                    seeds
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &seed)| play(i * chunk + j, seed))
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("A game panicked."))
+            .collect()
+    })
+}
+
+/// Plays games independent Matches from root with run_parallel. agents gets the index and seed
+/// of a game and returns fresh agents for Player 0 and Player 1, e.g. with the seats swapped in
+/// odd games.
+pub fn play_parallel<G, A0, A1, F>(
+    root: &G,
+    games: usize,
+    workers: usize,
+    seed: u64,
+    agents: F,
+) -> Vec<(G, G::Outcome)>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Send> + Clone + Send + Sync,
+    A0: Agent<G>,
+    A1: Agent<G>,
+    F: Fn(usize, u64) -> (A0, A1) + Sync,
+{
+    run_parallel(games, workers, seed, |game, seed| {
+        let (player0, player1) = agents(game, seed);
+        Match::new(root.clone(), player0, player1).play()
+    })
+}
 
 #[derive(Debug)]
 pub struct GamePlayer<G, E, S>
@@ -52,72 +379,188 @@ where
     }
 
     pub fn play(&mut self) -> (G, G::Outcome) {
-        loop {
-            let best_action = self.strategy.choose(&self.state, &mut self.evaluator);
-            match self.state.apply(&best_action) {
-                Ongoing(new_state) => {
-                    self.state = new_state;
-                }
-                Finished(new_state, outcome) => return (new_state, outcome),
-            }
-        }
+        self.run(&mut [], no_evaluations, |_, _| None)
+    }
+
+    /// Plays like play, invoking the observers along the way.
+    pub fn play_observed(
+        &mut self,
+        observers: &mut [&mut dyn Observer<G, E::Evaluation>],
+    ) -> (G, G::Outcome)
+    where
+        G::Action: Clone,
+    {
+        self.run(observers, evaluate_all, |_, _| None)
+    }
+
+    /// Plays like play, and also returns the Trajectory of the game.
+    pub fn play_recorded(&mut self) -> (G, G::Outcome, Trajectory)
+    where
+        G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions,
+    {
+        let mut trajectory = Trajectory::new();
+        let (state, outcome) = self.run(&mut [&mut trajectory], no_evaluations, |_, _| None);
+        (state, outcome, trajectory)
     }
 
     pub fn play_display(&mut self) -> (G, G::Outcome)
     where
         G: Display,
     {
-        loop {
-            print!("{}", self.state);
-            let best_action = self.strategy.choose(&self.state, &mut self.evaluator);
-            match self.state.apply(&best_action) {
-                Ongoing(new_state) => {
-                    self.state = new_state;
-                }
-                Finished(new_state, outcome) => {
-                    print!("{}", new_state);
-                    return (new_state, outcome);
-                }
-            }
-        }
+        self.run(&mut [&mut ConsoleObserver], no_evaluations, |_, _| None)
     }
 
+    /// Plays against a person at the terminal. Use a Match with a HumanAgent to choose the
+    /// human's seat by player rather than by who moves first.
     pub fn play_interactive(&mut self, player_starts: bool) -> (G, G::Outcome)
     where
         G: Display + Interactive,
     {
-        print!("{}", self.state);
-        if player_starts {
-            let action = self.state.get_user_input();
-            match self.state.apply(&action) {
-                Ongoing(new_state) => {
-                    self.state = new_state;
-                }
-                Finished(new_state, outcome) => return (new_state, outcome),
-            }
-            print!("{}", self.state);
-        }
-        loop {
-            let best_action = self.strategy.choose(&self.state, &mut self.evaluator);
-            match self.state.apply(&best_action) {
-                Ongoing(new_state) => self.state = new_state,
-                Finished(new_state, outcome) => {
-                    print!("{}", new_state);
-                    return (new_state, outcome);
-                }
-            }
-            print!("{}", self.state);
-            let action = self.state.get_user_input();
-            match self.state.apply(&action) {
-                Ongoing(new_state) => {
-                    self.state = new_state;
+        self.run(&mut [&mut ConsoleObserver], no_evaluations, |state, ply| {
+            (ply.is_multiple_of(2) == player_starts).then(|| state.get_user_input())
+        })
+    }
+
+    /// Plays with play_loop. evaluate computes the evaluations for observers that want them, and
+    /// at every ply, human may return the action of a person, and otherwise the strategy
+    /// chooses.
+    fn run<F>(
+        &mut self,
+        observers: &mut [&mut dyn Observer<G, E::Evaluation>],
+        evaluate: EvaluateAll<G, E>,
+        mut human: F,
+    ) -> (G, G::Outcome)
+    where
+        F: FnMut(&G, usize) -> Option<G::Action>,
+    {
+        play_loop(
+            &mut self.state,
+            observers,
+            &mut (&mut self.evaluator, &mut self.strategy),
+            |state, (evaluator, _)| evaluate(state, evaluator),
+            |state, ply, (evaluator, strategy)| match human(state, ply) {
+                Some(action) => (action, None),
+                None => {
+                    let action = strategy.choose(state, evaluator);
+                    (action, Some(take_stats(*evaluator, *strategy)))
                 }
-                Finished(new_state, outcome) => {
-                    print!("{}", new_state);
-                    return (new_state, outcome);
+            },
+        )
+    }
+}
+
+/// Computes the evaluations of the legal actions for observers, as evaluate_all and
+/// no_evaluations do.
+type EvaluateAll<G, E> =
+    fn(&G, &mut E) -> Vec<(<G as GameState>::Action, <E as Evaluator<G>>::Evaluation)>;
+
+/// The statistics of a move chosen by strategy with evaluator. The evaluator's are merged last,
+/// so that they provide the cache size.
+fn take_stats<G, E, S>(evaluator: &mut E, strategy: &mut S) -> SearchStats
+where
+    G: GameState,
+    E: Evaluator<G>,
+    S: Strategy<G, E>,
+{
+    let mut stats = strategy.take_stats();
+    stats.merge(&evaluator.take_stats());
+    stats
+}
+
+/// The evaluation of every legal action, for observers that want evaluations.
+fn evaluate_all<G, E>(state: &G, evaluator: &mut E) -> Vec<(G::Action, E::Evaluation)>
+where
+    G: GameState,
+    G::Action: Clone,
+    E: Evaluator<G>,
+{
+    state
+        .legal_actions()
+        .map(|action| (action.clone(), evaluator.evaluate(state, action)))
+        .collect()
+}
+
+/// Skips the evaluations where every observer is known not to want them, which doesn't need
+/// G::Action: Clone.
+fn no_evaluations<G, E>(_state: &G, _evaluator: &mut E) -> Vec<(G::Action, E::Evaluation)>
+where
+    G: GameState,
+    E: Evaluator<G>,
+{
+    vec![]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_player::{play_parallel, Match, StrategyAgent},
+        game_state::{
+            outcome::WinDraw::{self, *},
+            player::TwoPlayer,
+        },
+        games::tic_tac_toe::{Piece, TicTacToe},
+        strategy::GreedyStrategy,
+    };
+
+    #[test]
+    fn minimax_never_loses_in_either_seat() {
+        let mut minimax = StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy);
+        let mut random = StrategyAgent::new(RandomEvaluator::new(6), GreedyStrategy);
+        let mut wins = 0;
+        for game in 0..40usize {
+            let minimax_player = TwoPlayer::new(game.is_multiple_of(2));
+            let root = TicTacToe::new(Piece::X);
+            let mut game_match = if minimax_player.index() == 0 {
+                Match::new(root, &mut minimax, &mut random)
+            } else {
+                Match::new(root, &mut random, &mut minimax)
+            };
+            let (_, outcome): (_, WinDraw<TicTacToe>) = game_match.play();
+            match outcome {
+                Win(player) => {
+                    assert_eq!(player, minimax_player);
+                    wins += 1;
                 }
+                Draw => (),
             }
-            print!("{}", self.state);
         }
+        assert!(wins > 20, "{}", wins);
+    }
+
+    #[test]
+    fn swapped_seats() {
+        let root = TicTacToe::new(Piece::X);
+        let mut game_match = Match::new(
+            root,
+            StrategyAgent::new(RandomEvaluator::new(1), GreedyStrategy),
+            StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy),
+        );
+        game_match.swap_seats();
+        // Minimax now plays first and RandomEvaluator can't beat it.
+        for _ in 0..10 {
+            game_match.state = root;
+            let (_, outcome) = game_match.play();
+            assert_ne!(outcome, Win(TwoPlayer::new(false)));
+        }
+    }
+
+    #[test]
+    fn parallel_games_are_deterministic() {
+        let root = TicTacToe::new(Piece::X);
+        let play = |workers| {
+            play_parallel(&root, 25, workers, 7, |_, seed| {
+                (
+                    StrategyAgent::new(RandomEvaluator::new(seed), GreedyStrategy),
+                    StrategyAgent::new(RandomEvaluator::new(seed + 1), GreedyStrategy),
+                )
+            })
+        };
+        let single = play(1);
+        assert_eq!(single.len(), 25);
+        // Independent seeds give different games.
+        assert!(single.iter().any(|(state, _)| *state != single[0].0));
+        assert_eq!(play(4), single);
+        assert_eq!(play(32), single);
     }
 }
@@ -4,15 +4,22 @@ pub mod player;
 
 pub use ApplyResult::*;
 
-use self::{outcome::WinDraw, player::TwoPlayer};
+use std::hash::Hash;
+
+use self::{
+    outcome::{Payoff, WinDraw},
+    player::TwoPlayer,
+};
+use crate::rng::Rng;
 
 /// The result of applying an action to the Game.
 pub enum ApplyResult<G>
 where
     G: GameState,
 {
-    ///
+    /// The Game is not over; contains the next state.
     Ongoing(G),
+    /// The Game is over; contains the terminal state and its Outcome.
     Finished(G, G::Outcome),
 }
 
@@ -47,20 +54,35 @@ pub trait GameState: Sized {
 /// chess, checkers, tic-tac-toe, Connect 4 etc. where there is a fixed set of actions known at
 /// compile-time.
 pub trait EnumerableActions: GameState {
+    /// The number of possible actions. Action indices are in 0..N_ACTIONS.
+    const N_ACTIONS: usize;
+
     /// Returns the index of the given action. This is useful for evaluators
     fn action_index(&self, action: &Self::Action) -> usize;
 }
 
-/// Allows the user to separate applying an action and checking its outcome.
+/// Trait for Games that can describe a state as a fixed length vector of numbers, for learning
+/// algorithms that approximate values with a function of these features.
 ///
-/// This is useful if computing the outcome is expensive, and it would be faster to instead do a
-/// lookup and see if the outcome has already been computed. It is also useful in scenarios where
-/// you have an action history and want to compute the resulting state from the history without
-/// unnecessarily checking the outcome.
-///
-/// However, With great power comes great responsibility! Applying an action to a game that is
-/// finished will lead to weird results. It is up to the caller to ensure that this does not
-/// happen.
+/// Features should be from the perspective of the current player, e.g. "my pieces" and "their
+/// pieces" rather than "X" and "O", so that the same function serves both players.
+pub trait Features: GameState {
+    /// The length of the vectors returned by features().
+    const N_FEATURES: usize;
+
+    fn features(&self) -> Vec<f64>;
+}
+
+// Allows the user to separate applying an action and checking its outcome.
+//
+// This is useful if computing the outcome is expensive, and it would be faster to instead do a
+// lookup and see if the outcome has already been computed. It is also useful in scenarios where
+// you have an action history and want to compute the resulting state from the history without
+// unnecessarily checking the outcome.
+//
+// However, With great power comes great responsibility! Applying an action to a game that is
+// finished will lead to weird results. It is up to the caller to ensure that this does not
+// happen.
 // pub trait ApplyUnchecked {
 //     type Action;
 //     type Outcome;
@@ -115,6 +137,36 @@ pub trait PartialInformation: GameState {
     fn view_as(&self, player: &Self::Player) -> Self::PlayerView;
 }
 
+/// Trait for Games where the current player may not know the exact state of the Game, but can
+/// sample a state that is consistent with everything they have observed so far. These sampled
+/// states (determinizations) can then be searched as if the Game had perfect information.
+///
+/// For Games with perfect information the only consistent state is the state itself.
+pub trait Determinize: GameState {
+    fn determinize(&self, rng: &mut Rng) -> Self;
+}
+
+/// Trait for two player zero sum Games in extensive form: a tree of decision and chance nodes in
+/// which each player only knows which of their information sets they are in. This is the view of
+/// a Game that counterfactual regret minimization works with.
+///
+/// The information set of the current player is their PlayerView. At chance nodes
+/// legal_actions() returns the possible outcomes of chance and current_player() is meaningless.
+pub trait ExtensiveForm:
+    PartialInformation<
+        Action: Clone,
+        Player = TwoPlayer,
+        PlayerView: Hash + Eq + Clone,
+        Outcome: Payoff<TwoPlayer>,
+    > + Clone
+{
+    /// Returns true if the next action is drawn by chance rather than chosen by a player.
+    fn is_chance(&self) -> bool;
+
+    /// Returns the probability that chance picks the given action. Only called at chance nodes.
+    fn chance_probability(&self, action: &Self::Action) -> f64;
+}
+
 /// Used to play interactive games.
 ///
 /// The function should only return legal actions. If the user enters something that would be
@@ -1,4 +1,4 @@
-use super::*;
+use super::{player::TwoPlayer, *};
 
 /// The type of a Game outcome where there either is one definite winner or a draw. This is for
 /// Games like chess, checkers, tic-tac-toe, Monopoly etc.
@@ -10,3 +10,38 @@ where
     Win(G::Player),
     Draw,
 }
+
+/// The outcome of a two player zero sum Game with numeric results, e.g. the chips won in poker.
+/// Holds the amount won by Player 0; Player 1 wins the negation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ZeroSum(pub f64);
+
+/// Outcomes that can be turned into a number for each player. Algorithms that average over many
+/// outcomes, like regret minimization, need this.
+pub trait Payoff<P> {
+    fn payoff(&self, player: &P) -> f64;
+}
+
+impl<G> Payoff<TwoPlayer> for WinDraw<G>
+where
+    G: GameState<Player = TwoPlayer>,
+{
+    /// 1 for a win, 0 for a draw and -1 for a loss.
+    fn payoff(&self, player: &TwoPlayer) -> f64 {
+        match self {
+            WinDraw::Win(winner) if winner == player => 1.0,
+            WinDraw::Draw => 0.0,
+            WinDraw::Win(_) => -1.0,
+        }
+    }
+}
+
+impl Payoff<TwoPlayer> for ZeroSum {
+    fn payoff(&self, player: &TwoPlayer) -> f64 {
+        if player.index() == 0 {
+            self.0
+        } else {
+            -self.0
+        }
+    }
+}
@@ -1,13 +1,10 @@
-use std::ops::Index;
+use std::fmt::Display;
+use std::ops::{Index, IndexMut};
 
-/// An implementation of Connect 4.
-///
-/// This game is solved, and we know that Player 1 has a winning strategy. Using a minimax
-/// evaluator and greedy strategy should always guarantee a win for Player 1. Sorry Player 2!
 use crate::game_state::outcome::WinDraw;
 use crate::game_state::player::TwoPlayer;
 use crate::game_state::ApplyResult;
-use crate::game_state::GameState;
+use crate::game_state::{EnumerableActions, Features, GameState};
 
 const BOARD_WIDTH: usize = 7;
 const BOARD_HEIGHT: usize = 6;
@@ -15,28 +12,45 @@ const FULL_ROW: u8 = 0b1111111;
 const FIRST_FOUR: u8 = 0b1111;
 
 type Column = u8;
-type RowIdx = u8;
+type RowIdx = usize;
 
+/// The pieces of one player. Row 0 is the bottom row, and bit i of a row is set if the player
+/// has a piece in column i.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct BitBoard([u8; BOARD_HEIGHT]);
 
-pub struct BoardRow(u8);
-
 impl Index<RowIdx> for BitBoard {
-    type Output = BoardRow;
+    type Output = u8;
 
     fn index(&self, index: RowIdx) -> &Self::Output {
-        todo!()
+        &self.0[index]
+    }
+}
+
+impl IndexMut<RowIdx> for BitBoard {
+    fn index_mut(&mut self, index: RowIdx) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl BitBoard {
+    #[inline]
+    fn occupies(&self, row: RowIdx, column: usize) -> bool {
+        (self[row] >> column) & 1 == 1
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// An implementation of Connect 4.
+///
+/// This game is solved, and we know that Player 1 has a winning strategy. Using a minimax
+/// evaluator and greedy strategy should always guarantee a win for Player 1. Sorry Player 2!
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
 pub struct Connect4 {
     board: [BitBoard; 2],
     current_player: TwoPlayer,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Action(Column);
 
 pub static ALL_MOVES: [Action; BOARD_WIDTH] = [
@@ -49,6 +63,25 @@ pub static ALL_MOVES: [Action; BOARD_WIDTH] = [
     Action(6),
 ];
 
+impl Display for Connect4 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows().rev() {
+            for column in 0..BOARD_WIDTH {
+                let piece = if self.board[0].occupies(row, column) {
+                    "X"
+                } else if self.board[1].occupies(row, column) {
+                    "O"
+                } else {
+                    "_"
+                };
+                write!(f, "{}", piece)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "0123456\n")
+    }
+}
+
 impl Connect4 {
     pub fn new() -> Self {
         Default::default()
@@ -67,16 +100,26 @@ impl Connect4 {
     }
 
     pub fn is_legal(&self, action: &Action) -> bool {
-        self.col_is_empty(BOARD_HEIGHT, action)
+        (action.0 as usize) < BOARD_WIDTH && self.col_is_empty(BOARD_HEIGHT - 1, action)
     }
 
-    fn rows(&self) -> impl Iterator<Item = Row> {
-        (0..BOARD_HEIGHT).into_iter()
+    /// Returns the pieces of the player with the given index.
+    pub fn board(&self, player_index: usize) -> BitBoard {
+        self.board[player_index]
+    }
+
+    /// Returns true if the player with the given index has a piece in the given row and column.
+    pub fn occupies(&self, player_index: usize, row: usize, column: usize) -> bool {
+        self.board[player_index].occupies(row, column)
+    }
+
+    fn rows(&self) -> impl DoubleEndedIterator<Item = RowIdx> {
+        0..BOARD_HEIGHT
     }
 
     /// Bitwise OR the two rows together, shift right by the column number, and check if the
     /// rightmost bit is 0.
-    fn col_is_empty(&self, row: Row, action: &Action) -> bool {
+    fn col_is_empty(&self, row: RowIdx, action: &Action) -> bool {
         (((self.board[0][row] | self.board[1][row]) >> action.0) & 1) == 0
     }
 
@@ -86,8 +129,8 @@ impl Connect4 {
     }
 
     /// Computes the outcome of the game, if there is one. For Connect4, We only need to check if
-    /// the last player won.
-    fn outcome(&self, row: Row, action: &Action) -> Option<WinDraw<Self>> {
+    /// the last player won, and only lines through the piece they just placed.
+    fn outcome(&self, row: RowIdx, action: &Action) -> Option<WinDraw<Self>> {
         let last_player = self.current_player.last();
         let board = self.board[last_player.index()];
         if self.row_winner(board[row])
@@ -106,28 +149,41 @@ impl Connect4 {
         (0..4).any(|shift| ((board_row >> shift) & FIRST_FOUR) == FIRST_FOUR)
     }
 
-    fn col_winner(&self, row: Row, action: &Action, board: [u8; BOARD_HEIGHT]) -> bool {
+    fn col_winner(&self, row: RowIdx, action: &Action, board: BitBoard) -> bool {
         if row < 3 {
             false
         } else {
-            (1..4).all(|i| ((board[row - i] >> action.0) & 1) == 1)
+            (1..4).all(|i| board.occupies(row - i, action.0 as usize))
         }
     }
 
-    fn diag_winner(&self, row: usize, action: &Action, board: [u8; BOARD_HEIGHT]) -> bool {
-        todo!()
+    /// Counts the pieces in a row in both directions along the two diagonals through the given
+    /// square.
+    fn diag_winner(&self, row: RowIdx, action: &Action, board: BitBoard) -> bool {
+        let (row, column) = (row as isize, action.0 as isize);
+        let run = |d_row: isize, d_col: isize| {
+            (1..4)
+                .take_while(|&i| {
+                    let (r, c) = (row + i * d_row, column + i * d_col);
+                    (0..BOARD_HEIGHT as isize).contains(&r)
+                        && (0..BOARD_WIDTH as isize).contains(&c)
+                        && board.occupies(r as usize, c as usize)
+                })
+                .count()
+        };
+        run(1, 1) + run(-1, -1) >= 3 || run(1, -1) + run(-1, 1) >= 3
     }
 
     fn top_row_full(&self) -> bool {
-        (self.board[0][BOARD_HEIGHT] | self.board[1][BOARD_HEIGHT]) == FULL_ROW
+        (self.board[0][BOARD_HEIGHT - 1] | self.board[1][BOARD_HEIGHT - 1]) == FULL_ROW
     }
 
-    fn apply_action(&self, action: &Action) -> (Self, Row) {
+    fn apply_action(&self, action: &Action) -> (Self, RowIdx) {
         let mut new_board = self.board;
         let row = self
             .first_empty_row(action)
             .expect("Expected column to be empty.");
-        new_board[self.current_player_index()][row] |= action.0;
+        new_board[self.current_player_index()][row] |= 1 << action.0;
         let next_player = self.current_player.next();
         (
             Self {
@@ -163,3 +219,112 @@ impl GameState for Connect4 {
         self.current_player
     }
 }
+
+impl EnumerableActions for Connect4 {
+    const N_ACTIONS: usize = BOARD_WIDTH;
+
+    fn action_index(&self, action: &Self::Action) -> usize {
+        action.0 as usize
+    }
+}
+
+impl Features for Connect4 {
+    /// A bias, the squares of the current player, the squares of the opponent, and the number of
+    /// windows of four squares holding three and two of the pieces of only the current player or
+    /// only the opponent, scaled down to be comparable to the other features.
+    const N_FEATURES: usize = 1 + 2 * BOARD_WIDTH * BOARD_HEIGHT + 4;
+
+    fn features(&self) -> Vec<f64> {
+        let mine = self.board[self.current_player.index()];
+        let theirs = self.board[self.current_player.last().index()];
+        let mut features = Vec::with_capacity(Self::N_FEATURES);
+        features.push(1.0);
+        for board in [mine, theirs] {
+            for row in self.rows() {
+                features.extend(
+                    (0..BOARD_WIDTH).map(|column| board.occupies(row, column) as u8 as f64),
+                );
+            }
+        }
+        let mut windows = [0.0; 4];
+        for (d_row, d_col) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+            for row in 0..BOARD_HEIGHT as isize {
+                for column in 0..BOARD_WIDTH as isize {
+                    let squares: Vec<(isize, isize)> = (0..4)
+                        .map(|i| (row + i * d_row, column + i * d_col))
+                        .collect();
+                    if !squares.iter().all(|&(r, c)| {
+                        (0..BOARD_HEIGHT as isize).contains(&r)
+                            && (0..BOARD_WIDTH as isize).contains(&c)
+                    }) {
+                        continue;
+                    }
+                    let count = |board: &BitBoard| {
+                        squares
+                            .iter()
+                            .filter(|&&(r, c)| board.occupies(r as usize, c as usize))
+                            .count()
+                    };
+                    match (count(&mine), count(&theirs)) {
+                        (3, 0) => windows[0] += 0.1,
+                        (0, 3) => windows[1] += 0.1,
+                        (2, 0) => windows[2] += 0.1,
+                        (0, 2) => windows[3] += 0.1,
+                        _ => (),
+                    }
+                }
+            }
+        }
+        features.extend(windows);
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game_state::{outcome::WinDraw, player::TwoPlayer, ApplyResult, GameState},
+        games::connect4::{Connect4, ALL_MOVES},
+    };
+
+    fn play(columns: &[usize]) -> Option<WinDraw<Connect4>> {
+        let mut state = Connect4::new();
+        for (i, &column) in columns.iter().enumerate() {
+            match state.apply(&ALL_MOVES[column]) {
+                ApplyResult::Ongoing(next_state) => state = next_state,
+                ApplyResult::Finished(_, outcome) => {
+                    assert_eq!(i, columns.len() - 1, "The game ended early.");
+                    return Some(outcome);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_wins() {
+        let player0 = Some(WinDraw::Win(TwoPlayer::new(true)));
+        // Horizontal.
+        assert_eq!(play(&[0, 0, 1, 1, 2, 2, 3]), player0);
+        // Vertical.
+        assert_eq!(play(&[4, 5, 4, 5, 4, 5, 4]), player0);
+        // Diagonal up and to the right.
+        assert_eq!(play(&[0, 1, 1, 2, 2, 3, 2, 3, 3, 6, 3]), player0);
+        // Diagonal up and to the left.
+        assert_eq!(play(&[6, 5, 5, 4, 4, 3, 4, 3, 3, 0, 3]), player0);
+        assert_eq!(play(&[0, 1, 2, 3, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_full_column() {
+        let mut state = Connect4::new();
+        for _ in 0..6 {
+            assert!(state.is_legal(&ALL_MOVES[2]));
+            state = match state.apply(&ALL_MOVES[2]) {
+                ApplyResult::Ongoing(next_state) => next_state,
+                ApplyResult::Finished(..) => unreachable!(),
+            };
+        }
+        assert!(!state.is_legal(&ALL_MOVES[2]));
+    }
+}
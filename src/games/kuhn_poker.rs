@@ -0,0 +1,259 @@
+use std::fmt::Display;
+
+use crate::{
+    game_state::{
+        outcome::ZeroSum,
+        player::TwoPlayer,
+        ApplyResult::{self, *},
+        Determinize, ExtensiveForm, GameState, PartialInformation,
+    },
+    rng::Rng,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Card {
+    Jack,
+    Queen,
+    King,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// The chance action dealing the given cards to Player 0 and Player 1.
+    Deal(Card, Card),
+    /// Check, or fold when facing a bet.
+    Pass,
+    /// Bet, or call when facing a bet.
+    Bet,
+}
+
+/// Every way to deal two of the three cards, each of which is equally likely.
+pub static ALL_DEALS: [Action; 6] = [
+    Action::Deal(Card::Jack, Card::Queen),
+    Action::Deal(Card::Jack, Card::King),
+    Action::Deal(Card::Queen, Card::Jack),
+    Action::Deal(Card::Queen, Card::King),
+    Action::Deal(Card::King, Card::Jack),
+    Action::Deal(Card::King, Card::Queen),
+];
+
+/// The betting actions, available at every decision node.
+pub static BETTING_ACTIONS: [Action; 2] = [Action::Pass, Action::Bet];
+
+/// An implementation of Kuhn poker, the smallest interesting poker game.
+///
+/// There are three cards, Jack < Queen < King. Both players ante one chip and are dealt one card
+/// each. Player 0 then either passes or bets one chip. After a pass, Player 1 may pass, ending in
+/// a showdown for the antes, or bet, after which Player 0 must call or fold. After a bet the
+/// opponent must call (Bet) or fold (Pass). The game value for Player 0 is -1/18.
+///
+/// The game starts at a chance node where both cards are dealt at once. Use KuhnPoker::deal to
+/// start from a known deal instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KuhnPoker {
+    cards: Option<[Card; 2]>,
+    history: Vec<Action>,
+    current_player: TwoPlayer,
+}
+
+/// What a player knows: their own card and the betting so far. This is also their information
+/// set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KuhnView {
+    pub card: Option<Card>,
+    pub history: Vec<Action>,
+}
+
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let card = match *self {
+            Card::Jack => "J",
+            Card::Queen => "Q",
+            Card::King => "K",
+        };
+        write!(f, "{}", card)
+    }
+}
+
+impl Display for KuhnPoker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.cards {
+            Some([card0, card1]) => write!(f, "{} {} |", card0, card1)?,
+            None => write!(f, "? ? |")?,
+        }
+        for action in &self.history {
+            match action {
+                Action::Pass => write!(f, " p")?,
+                Action::Bet => write!(f, " b")?,
+                Action::Deal(..) => (),
+            }
+        }
+        writeln!(f)
+    }
+}
+
+impl KuhnPoker {
+    /// Starts a new Game before the cards are dealt.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts a new Game with the given cards for Player 0 and Player 1.
+    pub fn deal(card0: Card, card1: Card) -> Self {
+        Self {
+            cards: Some([card0, card1]),
+            ..Default::default()
+        }
+    }
+
+    pub fn cards(&self) -> Option<[Card; 2]> {
+        self.cards
+    }
+
+    /// The betting history, without the deal.
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+
+    pub fn is_legal(&self, action: &Action) -> bool {
+        match action {
+            Action::Deal(card0, card1) => self.cards.is_none() && card0 != card1,
+            Action::Pass | Action::Bet => self.cards.is_some(),
+        }
+    }
+
+    /// Returns the amount won by Player 0 if the betting is over, None otherwise.
+    pub fn outcome(&self) -> Option<ZeroSum> {
+        use Action::*;
+        let [card0, card1] = self.cards?;
+        let showdown = if card0 > card1 { 1.0 } else { -1.0 };
+        match self.history[..] {
+            [Pass, Pass] => Some(ZeroSum(showdown)),
+            [Bet, Pass] => Some(ZeroSum(1.0)),
+            [Pass, Bet, Pass] => Some(ZeroSum(-1.0)),
+            [Bet, Bet] | [Pass, Bet, Bet] => Some(ZeroSum(2.0 * showdown)),
+            _ => None,
+        }
+    }
+
+    /// Applies the given action, assuming that it is legal.
+    pub fn apply_unchecked(&self, action: &Action) -> Self {
+        let mut next_state = self.clone();
+        match *action {
+            Action::Deal(card0, card1) => next_state.cards = Some([card0, card1]),
+            betting_action => {
+                next_state.history.push(betting_action);
+                next_state.current_player.next_mut();
+            }
+        }
+        next_state
+    }
+}
+
+impl GameState for KuhnPoker {
+    type Action = Action;
+
+    type Player = TwoPlayer;
+
+    type Outcome = ZeroSum;
+
+    fn apply(&self, action: &Self::Action) -> ApplyResult<Self> {
+        let next_state = self.apply_unchecked(action);
+        match next_state.outcome() {
+            Some(outcome) => Finished(next_state, outcome),
+            None => Ongoing(next_state),
+        }
+    }
+
+    fn legal_actions(&self) -> impl Iterator<Item = &Self::Action> {
+        match self.cards {
+            None => ALL_DEALS.iter(),
+            Some(_) => BETTING_ACTIONS.iter(),
+        }
+    }
+
+    fn current_player(&self) -> Self::Player {
+        self.current_player
+    }
+}
+
+impl PartialInformation for KuhnPoker {
+    type PlayerView = KuhnView;
+
+    fn view_as(&self, player: &Self::Player) -> Self::PlayerView {
+        KuhnView {
+            card: self.cards.map(|cards| cards[player.index()]),
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl ExtensiveForm for KuhnPoker {
+    fn is_chance(&self) -> bool {
+        self.cards.is_none()
+    }
+
+    fn chance_probability(&self, _action: &Self::Action) -> f64 {
+        1.0 / ALL_DEALS.len() as f64
+    }
+}
+
+impl Determinize for KuhnPoker {
+    /// Deals the opponent one of the two cards the current player does not hold.
+    fn determinize(&self, rng: &mut Rng) -> Self {
+        let mut state = self.clone();
+        if let Some(mut cards) = self.cards {
+            let me = self.current_player.index();
+            let unseen: Vec<Card> = [Card::Jack, Card::Queen, Card::King]
+                .into_iter()
+                .filter(|&card| card != cards[me])
+                .collect();
+            cards[1 - me] = *rng.choose(&unseen).expect("Two cards are always unseen.");
+            state.cards = Some(cards);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game_state::{outcome::ZeroSum, ApplyResult, GameState},
+        games::kuhn_poker::{Action::*, Card::*, KuhnPoker},
+    };
+
+    fn play(mut state: KuhnPoker, actions: &[crate::games::kuhn_poker::Action]) -> ZeroSum {
+        for action in actions {
+            match state.apply(action) {
+                ApplyResult::Ongoing(next_state) => state = next_state,
+                ApplyResult::Finished(_, outcome) => return outcome,
+            }
+        }
+        panic!("The game should be over.")
+    }
+
+    #[test]
+    fn test_payoffs() {
+        let root = KuhnPoker::new();
+        assert_eq!(
+            play(root.clone(), &[Deal(King, Jack), Pass, Pass]),
+            ZeroSum(1.0)
+        );
+        assert_eq!(
+            play(root.clone(), &[Deal(Jack, King), Bet, Pass]),
+            ZeroSum(1.0)
+        );
+        assert_eq!(
+            play(root.clone(), &[Deal(King, Jack), Pass, Bet, Pass]),
+            ZeroSum(-1.0)
+        );
+        assert_eq!(
+            play(root.clone(), &[Deal(Queen, King), Bet, Bet]),
+            ZeroSum(-2.0)
+        );
+        assert_eq!(
+            play(root, &[Deal(Queen, Jack), Pass, Bet, Bet]),
+            ZeroSum(2.0)
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead},
+};
+
+use super::kuhn_poker::Card;
+use crate::{
+    game_state::{
+        outcome::ZeroSum,
+        player::TwoPlayer,
+        ApplyResult::{self, *},
+        Determinize, ExtensiveForm, GameState, Interactive, PartialInformation,
+    },
+    rng::Rng,
+};
+
+/// The number of copies of each rank in the deck.
+const COPIES: usize = 2;
+const DECK_SIZE: usize = 3 * COPIES;
+/// The maximum number of bets and raises in one betting round.
+const MAX_RAISES: u8 = 2;
+/// The size of a bet or raise in each of the two betting rounds.
+const RAISE_SIZE: [u32; 2] = [2, 4];
+const RANKS: [Card; 3] = [Card::Jack, Card::Queen, Card::King];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// The chance action dealing the private cards of Player 0 and Player 1 and the public card.
+    Deal(Card, Card, Card),
+    Fold,
+    /// Check, or call when facing a bet.
+    Call,
+    /// Bet, or raise when facing a bet.
+    Raise,
+}
+
+/// Every way of dealing the three cards. No rank can be dealt three times since there are only
+/// two copies of each.
+pub static ALL_DEALS: [Action; 24] = all_deals();
+
+/// The betting actions. Which of them are legal depends on the state of the betting.
+pub static BETTING_ACTIONS: [Action; 3] = [Action::Fold, Action::Call, Action::Raise];
+
+const fn all_deals() -> [Action; 24] {
+    let mut deals = [Action::Fold; 24];
+    let mut i = 0;
+    let mut n = 0;
+    while i < 27 {
+        let (a, b, c) = (i / 9, (i / 3) % 3, i % 3);
+        if !(a == b && b == c) {
+            deals[n] = Action::Deal(RANKS[a], RANKS[b], RANKS[c]);
+            n += 1;
+        }
+        i += 1;
+    }
+    deals
+}
+
+/// An implementation of Leduc hold'em, a small poker game with two betting rounds that is a
+/// standard benchmark for imperfect information algorithms.
+///
+/// The deck has two Jacks, two Queens and two Kings. Both players ante one chip and are dealt one
+/// private card. After a betting round, one public card is revealed and there is a second betting
+/// round. Bets and raises are 2 chips in the first round and 4 in the second, with at most two of
+/// them per round. At the showdown a player whose card pairs the public card wins, otherwise the
+/// higher card wins, and equal cards split the pot.
+///
+/// All of the cards are dealt by a single chance action at the start of the game, and the public
+/// card stays hidden until the second round. Use LeducHoldem::deal to start from a known deal.
+///
+/// Only CFR and the other ExtensiveForm algorithms deal by chance_probability. Match, GamePlayer
+/// and the searches would let Player 0 choose the deal, so start them from a dealt state, or wrap
+/// the agents of a Match in ChanceAgent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LeducHoldem {
+    /// The private cards of Player 0 and Player 1, then the public card.
+    cards: Option<[Card; 3]>,
+    /// The betting actions of both rounds.
+    history: Vec<Action>,
+    round: usize,
+    /// The number of actions taken in the current round.
+    round_actions: usize,
+    /// The number of bets and raises in the current round.
+    raises: u8,
+    /// The chips each player has put into the pot.
+    contributions: [u32; 2],
+    current_player: TwoPlayer,
+}
+
+/// What a player knows: their private card, the public card once it is revealed, and the
+/// betting. This is also their information set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LeducView {
+    pub private: Option<Card>,
+    pub public: Option<Card>,
+    pub history: Vec<Action>,
+}
+
+impl Default for LeducHoldem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Deal(card0, card1, public) => write!(f, "{}{}{}", card0, card1, public),
+            Action::Fold => write!(f, "f"),
+            Action::Call => write!(f, "c"),
+            Action::Raise => write!(f, "r"),
+        }
+    }
+}
+
+impl Display for LeducHoldem {
+    /// Only displays public information.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.public_card() {
+            Some(card) => write!(f, "Board: {}", card)?,
+            None => write!(f, "Board: ?")?,
+        }
+        writeln!(
+            f,
+            " | Pot: {} | {}",
+            self.contributions[0] + self.contributions[1],
+            self.notation()
+        )
+    }
+}
+
+impl LeducHoldem {
+    /// Starts a new Game before the cards are dealt.
+    pub fn new() -> Self {
+        Self {
+            cards: None,
+            history: vec![],
+            round: 0,
+            round_actions: 0,
+            raises: 0,
+            contributions: [1, 1],
+            current_player: TwoPlayer::default(),
+        }
+    }
+
+    /// Starts a new Game with the given private cards of Player 0 and Player 1 and the given
+    /// public card.
+    pub fn deal(card0: Card, card1: Card, public: Card) -> Self {
+        Self {
+            cards: Some([card0, card1, public]),
+            ..Self::new()
+        }
+    }
+
+    pub fn private_card(&self, player: &TwoPlayer) -> Option<Card> {
+        self.cards.map(|cards| cards[player.index()])
+    }
+
+    /// Returns the public card if it has been revealed.
+    pub fn public_card(&self) -> Option<Card> {
+        self.cards.filter(|_| self.round == 1).map(|cards| cards[2])
+    }
+
+    pub fn contributions(&self) -> [u32; 2] {
+        self.contributions
+    }
+
+    /// The betting actions of both rounds.
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+
+    /// The betting in the usual poker notation, e.g. "rc/crc": r for a bet or raise, c for a
+    /// check or call, f for a fold and / between the rounds.
+    pub fn notation(&self) -> String {
+        let mut notation = String::new();
+        let mut round_actions = 0;
+        for action in &self.history {
+            notation.push_str(&action.to_string());
+            round_actions += 1;
+            if notation.contains('/') {
+                continue;
+            }
+            // The first round ends with a call that isn't its first action.
+            if *action == Action::Call && round_actions > 1 {
+                notation.push('/');
+            }
+        }
+        notation
+    }
+
+    fn facing_bet(&self) -> bool {
+        self.contributions[0] != self.contributions[1]
+    }
+
+    pub fn is_legal(&self, action: &Action) -> bool {
+        match (self.cards, action) {
+            (None, Action::Deal(card0, card1, public)) => !(card0 == card1 && card1 == public),
+            (Some(_), Action::Fold) => self.facing_bet(),
+            (Some(_), Action::Call) => true,
+            (Some(_), Action::Raise) => self.raises < MAX_RAISES,
+            _ => false,
+        }
+    }
+
+    /// Applies the given action, assuming that it is legal. Returns the next state and, if the
+    /// game is over, the amount won by Player 0.
+    pub fn apply_unchecked(&self, action: &Action) -> (Self, Option<ZeroSum>) {
+        let mut next_state = self.clone();
+        let me = self.current_player.index();
+        let opponent = 1 - me;
+        match *action {
+            Action::Deal(card0, card1, public) => {
+                next_state.cards = Some([card0, card1, public]);
+                return (next_state, None);
+            }
+            Action::Fold => {
+                next_state.history.push(*action);
+                // The folding player loses what they put in the pot.
+                let lost = self.contributions[me] as f64;
+                let outcome = if me == 0 { -lost } else { lost };
+                return (next_state, Some(ZeroSum(outcome)));
+            }
+            Action::Call => next_state.contributions[me] = self.contributions[opponent],
+            Action::Raise => {
+                next_state.contributions[me] =
+                    self.contributions[opponent] + RAISE_SIZE[self.round];
+                next_state.raises += 1;
+            }
+        }
+        next_state.history.push(*action);
+        next_state.round_actions += 1;
+        next_state.current_player.next_mut();
+        let round_over = *action == Action::Call && next_state.round_actions > 1;
+        if !round_over {
+            return (next_state, None);
+        }
+        if self.round == 1 {
+            let outcome = next_state.showdown();
+            return (next_state, Some(outcome));
+        }
+        next_state.round = 1;
+        next_state.round_actions = 0;
+        next_state.raises = 0;
+        next_state.current_player = TwoPlayer::default();
+        (next_state, None)
+    }
+
+    /// Compares the hands once all of the betting is done.
+    fn showdown(&self) -> ZeroSum {
+        let [card0, card1, public] = self.cards.expect("The cards have been dealt.");
+        let strength = |card: Card| (card == public, card);
+        let pot = self.contributions[0] as f64;
+        match strength(card0).cmp(&strength(card1)) {
+            std::cmp::Ordering::Greater => ZeroSum(pot),
+            std::cmp::Ordering::Less => ZeroSum(-pot),
+            std::cmp::Ordering::Equal => ZeroSum(0.0),
+        }
+    }
+
+    /// The cards that are still in the deck after the given cards have been dealt.
+    fn remaining(dealt: &[Card]) -> Vec<Card> {
+        RANKS
+            .iter()
+            .flat_map(|&rank| {
+                let left = COPIES - dealt.iter().filter(|&&card| card == rank).count();
+                std::iter::repeat_n(rank, left)
+            })
+            .collect()
+    }
+}
+
+impl GameState for LeducHoldem {
+    type Action = Action;
+
+    type Player = TwoPlayer;
+
+    type Outcome = ZeroSum;
+
+    fn apply(&self, action: &Self::Action) -> ApplyResult<Self> {
+        match self.apply_unchecked(action) {
+            (next_state, Some(outcome)) => Finished(next_state, outcome),
+            (next_state, None) => Ongoing(next_state),
+        }
+    }
+
+    fn legal_actions(&self) -> impl Iterator<Item = &Self::Action> {
+        let actions = match self.cards {
+            None => ALL_DEALS.iter(),
+            Some(_) => BETTING_ACTIONS.iter(),
+        };
+        actions.filter(|&action| self.is_legal(action))
+    }
+
+    fn current_player(&self) -> Self::Player {
+        self.current_player
+    }
+}
+
+impl PartialInformation for LeducHoldem {
+    type PlayerView = LeducView;
+
+    fn view_as(&self, player: &Self::Player) -> Self::PlayerView {
+        LeducView {
+            private: self.private_card(player),
+            public: self.public_card(),
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl ExtensiveForm for LeducHoldem {
+    fn is_chance(&self) -> bool {
+        self.cards.is_none()
+    }
+
+    fn chance_probability(&self, action: &Self::Action) -> f64 {
+        let Action::Deal(card0, card1, public) = *action else {
+            return 0.0;
+        };
+        let mut dealt = vec![];
+        let mut probability = 1.0;
+        for card in [card0, card1, public] {
+            let remaining = Self::remaining(&dealt);
+            let copies = remaining.iter().filter(|&&left| left == card).count();
+            probability *= copies as f64 / remaining.len() as f64;
+            dealt.push(card);
+        }
+        probability
+    }
+}
+
+impl Determinize for LeducHoldem {
+    /// Deals the opponent, and the public card if it is still hidden, from the cards the current
+    /// player hasn't seen. Panics before the deal, where no player has seen anything and the
+    /// next action belongs to chance.
+    fn determinize(&self, rng: &mut Rng) -> Self {
+        assert!(
+            !self.is_chance(),
+            "The cards must be dealt before the game is determinized."
+        );
+        let mut state = self.clone();
+        if let Some(mut cards) = self.cards {
+            let me = self.current_player.index();
+            let mut seen = vec![cards[me]];
+            seen.extend(self.public_card());
+            let mut unseen = Self::remaining(&seen);
+            debug_assert_eq!(unseen.len(), DECK_SIZE - seen.len());
+            let i = rng.gen_index(unseen.len());
+            cards[1 - me] = unseen.swap_remove(i);
+            if self.public_card().is_none() {
+                cards[2] = *rng
+                    .choose(&unseen)
+                    .expect("There are cards left in the deck.");
+            }
+            state.cards = Some(cards);
+        }
+        state
+    }
+}
+
+impl Interactive for LeducHoldem {
+    /// Asks the current player for an action. Panics at the chance node, where no player acts,
+    /// so deal the cards first.
+    fn get_user_input(&self) -> Self::Action {
+        assert!(
+            !self.is_chance(),
+            "The cards must be dealt before a player is asked for an action."
+        );
+        if let Some(card) = self.private_card(&self.current_player) {
+            println!("Your card: {}. Enter f, c or r.", card);
+        }
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let action = match line.as_deref().map(str::trim) {
+                Ok("f") => Action::Fold,
+                Ok("c") => Action::Call,
+                Ok("r") => Action::Raise,
+                _ => {
+                    println!("Try again");
+                    continue;
+                }
+            };
+            if self.is_legal(&action) {
+                return action;
+            }
+            println!("Try again");
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cfr::{exploitability, CfrSolver, CfrVariant},
+        evaluator::RandomEvaluator,
+        game_player::{ChanceAgent, Match, StrategyAgent},
+        game_state::{
+            outcome::ZeroSum, player::TwoPlayer, ApplyResult, Determinize, ExtensiveForm,
+            GameState, Interactive,
+        },
+        games::{
+            kuhn_poker::Card::*,
+            leduc_holdem::{Action, Action::*, LeducHoldem, ALL_DEALS},
+        },
+        rng::Rng,
+        search::ismcts::IsmctsStrategy,
+        strategy::GreedyStrategy,
+    };
+
+    fn play(mut state: LeducHoldem, actions: &[Action]) -> (LeducHoldem, ZeroSum) {
+        for action in actions {
+            assert!(state.is_legal(action), "{:?} is illegal", action);
+            match state.apply(action) {
+                ApplyResult::Ongoing(next_state) => state = next_state,
+                ApplyResult::Finished(final_state, outcome) => return (final_state, outcome),
+            }
+        }
+        panic!("The game should be over.")
+    }
+
+    #[test]
+    fn test_payoffs() {
+        // Both check twice, the Queen pairs the board.
+        let (state, outcome) = play(LeducHoldem::deal(Queen, King, Queen), &[Call; 4]);
+        assert_eq!(outcome, ZeroSum(1.0));
+        assert_eq!(state.notation(), "cc/cc");
+        // Bet, raise, call, then bet and call: 1 + 4 + 4 = 9 each.
+        let (state, outcome) = play(
+            LeducHoldem::deal(Jack, King, Queen),
+            &[Raise, Raise, Call, Raise, Call],
+        );
+        assert_eq!(outcome, ZeroSum(-9.0));
+        assert_eq!(state.notation(), "rrc/rc");
+        // Equal cards split the pot.
+        let (_, outcome) = play(
+            LeducHoldem::deal(Jack, Jack, King),
+            &[Call, Raise, Call, Call, Call],
+        );
+        assert_eq!(outcome, ZeroSum(0.0));
+        // Folding loses what was put in the pot.
+        let (_, outcome) = play(LeducHoldem::deal(King, Jack, Queen), &[Raise, Raise, Fold]);
+        assert_eq!(outcome, ZeroSum(-3.0));
+    }
+
+    #[test]
+    fn test_betting_limits() {
+        let state = LeducHoldem::deal(Jack, Queen, King);
+        assert!(!state.is_legal(&Fold));
+        let (state, _) = state.apply_unchecked(&Raise);
+        let (state, _) = state.apply_unchecked(&Raise);
+        assert!(!state.is_legal(&Raise));
+        assert!(state.is_legal(&Fold));
+    }
+
+    #[test]
+    fn test_chance_probabilities_sum_to_one() {
+        let root = LeducHoldem::new();
+        let total: f64 = ALL_DEALS
+            .iter()
+            .map(|deal| root.chance_probability(deal))
+            .sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cfr_plus_reduces_exploitability() {
+        let root = LeducHoldem::new();
+        let mut solver = CfrSolver::new(root.clone(), CfrVariant::Plus, 0);
+        solver.run(1);
+        let initial = exploitability::exploitability(&root, &solver.average_policy());
+        solver.run(30);
+        let after = exploitability::exploitability(&root, &solver.average_policy());
+        assert!(after < initial / 4.0, "{} -> {}", initial, after);
+    }
+
+    #[test]
+    fn plays_a_match_from_before_the_deal() {
+        let mut game_match = Match::new(
+            LeducHoldem::new(),
+            ChanceAgent::new(
+                StrategyAgent::new(RandomEvaluator::new(1), IsmctsStrategy::new(50, 2)),
+                3,
+            ),
+            ChanceAgent::new(
+                StrategyAgent::new(RandomEvaluator::new(4), GreedyStrategy),
+                5,
+            ),
+        );
+        let (state, _) = game_match.play();
+        assert!(state.private_card(&TwoPlayer::new(true)).is_some());
+    }
+
+    #[test]
+    fn chance_agents_deal_by_chance_probability() {
+        // Both players hold the same rank with probability 1/5, against 1/4 for a uniform deal.
+        let games = 4000;
+        let mut pairs = 0;
+        for seed in 0..games {
+            let mut game_match = Match::new(
+                LeducHoldem::new(),
+                ChanceAgent::new(
+                    StrategyAgent::new(RandomEvaluator::new(seed), GreedyStrategy),
+                    seed,
+                ),
+                ChanceAgent::new(
+                    StrategyAgent::new(RandomEvaluator::new(seed + 1), GreedyStrategy),
+                    seed + 1,
+                ),
+            );
+            let (state, _) = game_match.play();
+            if state.private_card(&TwoPlayer::new(true))
+                == state.private_card(&TwoPlayer::new(false))
+            {
+                pairs += 1;
+            }
+        }
+        let frequency = pairs as f64 / games as f64;
+        assert!((frequency - 0.2).abs() < 0.02, "{}", frequency);
+    }
+
+    #[test]
+    #[should_panic(expected = "The cards must be dealt before the game is determinized")]
+    fn determinize_needs_dealt_cards() {
+        LeducHoldem::new().determinize(&mut Rng::new(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "The cards must be dealt")]
+    fn user_input_needs_dealt_cards() {
+        LeducHoldem::new().get_user_input();
+    }
+}
@@ -1,25 +1,26 @@
 use std::{
-    collections::HashMap,
     fmt::{Debug, Display},
-    io::{self, BufRead},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
     vec,
 };
 
 use crate::{
+    cache::{invalid_data, BoundedCache, Encode},
     evaluator::Evaluator,
     game_state::{
-        outcome::{
-            self,
-            WinDraw::{self, *},
-        },
-        player::{self, TwoPlayer},
-        ApplyResult, GameState, Interactive,
+        outcome::WinDraw::{self, *},
+        player::TwoPlayer,
+        ApplyResult, Determinize, GameState, Interactive,
     },
+    rng::Rng,
+    search::{SearchStats, StatsTracker},
 };
 
 use super::tic_tac_toe::*;
 
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Hash)]
 pub struct BitBoard(u16);
 
 impl BitBoard {
@@ -67,7 +68,36 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A tag byte, then the action if there is one.
+impl<T> Encode for Info<T>
+where
+    T: Encode,
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Info::Visible(action) => {
+                0u8.encode(writer)?;
+                action.encode(writer)
+            }
+            Info::Masked(action) => {
+                1u8.encode(writer)?;
+                action.encode(writer)
+            }
+            Info::Invisible => 2u8.encode(writer),
+        }
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(Info::Visible(T::decode(reader)?)),
+            1 => Ok(Info::Masked(T::decode(reader)?)),
+            2 => Ok(Info::Invisible),
+            _ => Err(invalid_data("Not an Info tag.")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MaskedTicTacToe<const N: usize> {
     board: [BitBoard; 2],
     masked: [Action; N],
@@ -164,6 +194,11 @@ impl<const N: usize> MaskedTicTacToe<N> {
         self.masked
     }
 
+    /// The masked squares as a bitmask, which doesn't depend on their order.
+    fn mask_bits(&self) -> u16 {
+        self.masked.iter().fold(0, |bits, action| bits | action.0)
+    }
+
     pub fn legal_masked(&self) -> impl Iterator<Item = &Action> {
         self.masked.iter().filter(|&action| self.is_legal(action))
     }
@@ -184,7 +219,7 @@ impl<const N: usize> MaskedTicTacToe<N> {
         let board = self.board[self.last_player().index()];
         WINNING_POSITIONS
             .iter()
-            .any(|&position| (position & board.0) == position)
+            .any(|&pos| board.is_occupied(&Action(pos)))
     }
 
     /// Here, we assume that we are given a legal action.
@@ -204,7 +239,7 @@ impl<const N: usize> MaskedTicTacToe<N> {
         Self {
             board,
             history,
-            no_action: no_action,
+            no_action,
             masked: self.masked,
             current_player: last_player,
             player1_piece: self.player1_piece,
@@ -326,6 +361,41 @@ impl<const N: usize> Interactive for MaskedTicTacToe<N> {
     }
 }
 
+impl<const N: usize> Determinize for MaskedTicTacToe<N> {
+    /// Samples one of the states in the superposition of the current player, i.e. one of the
+    /// states that MaskedEvaluator::superposition would return for our visible history. Rather
+    /// than enumerating them all, we replay the history and pick a random masked square for every
+    /// move that is invisible to us, starting over whenever the guesses contradict what we have
+    /// seen. The true state is always consistent, so this terminates.
+    fn determinize(&self, rng: &mut Rng) -> Self {
+        let history = self.visible_history();
+        'sample: loop {
+            let mut state = self.genesis();
+            for observed in &history {
+                let action = match observed {
+                    Info::Visible(action) | Info::Masked(action) => *action,
+                    Info::Invisible => {
+                        let candidates: Vec<Action> = state.legal_masked().copied().collect();
+                        match rng.choose(&candidates) {
+                            Some(&action) => action,
+                            None => continue 'sample,
+                        }
+                    }
+                };
+                if !state.is_legal(&action) {
+                    continue 'sample;
+                }
+                state.apply_unchecked_mut(&action);
+                // If the game were over we would already know it, so this guess is wrong.
+                if state.outcome().is_some() {
+                    continue 'sample;
+                }
+            }
+            return state;
+        }
+    }
+}
+
 impl<const N: usize> GameState for MaskedTicTacToe<N> {
     type Action = Action;
 
@@ -351,23 +421,94 @@ impl<const N: usize> GameState for MaskedTicTacToe<N> {
     }
 }
 
-#[derive(Debug, Clone)]
+const MASKED_MAGIC: &[u8; 8] = b"RFMASKD1";
+
+/// The key of the MaskedEvaluator cache: the visible history of the caller and the action
+/// they are evaluating.
+pub type HistoryAction = (Vec<Info<Action>>, Action);
+
+/// The cache isn't limited by default. A bounded cache evicts the least recently used
+/// histories, which are evaluated again if they come back.
+///
+/// The cache only depends on the visible histories, so a saved cache is only valid for games
+/// with the same masked squares as the ones it was filled with. save records them, and load
+/// checks them.
+#[derive(Debug, Clone, Default)]
 pub struct MaskedEvaluator {
-    pub visited: HashMap<(Vec<Info<Action>>, Action), (i8, i8)>,
+    pub visited: BoundedCache<HistoryAction, (i8, i8)>,
+    tracker: StatsTracker,
 }
 
 impl MaskedEvaluator {
     pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// An evaluator that caches at most capacity histories, which must be at least 2.
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            visited: HashMap::new(),
+            visited: BoundedCache::new(capacity),
+            ..Default::default()
         }
     }
 
+    /// Writes the cache to the file at path, so a later run can load it instead of solving
+    /// again. game is any state of the game the cache was filled with. A magic number and the
+    /// masked squares, as a bitmask of squares, are written before the cache.
+    pub fn save<P: AsRef<Path>, const N: usize>(
+        &self,
+        path: P,
+        game: &MaskedTicTacToe<N>,
+    ) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MASKED_MAGIC)?;
+        game.mask_bits().encode(&mut writer)?;
+        self.visited.encode(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads an evaluator whose cache was written by save, with the capacity it was saved with.
+    /// Returns an InvalidData error if the cache was filled with a game whose masked squares
+    /// differ from those of game, in any order.
+    pub fn load<P: AsRef<Path>, const N: usize>(
+        path: P,
+        game: &MaskedTicTacToe<N>,
+    ) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; MASKED_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *MASKED_MAGIC {
+            return Err(invalid_data("Not a MaskedEvaluator file."));
+        }
+        if u16::decode(&mut reader)? != game.mask_bits() {
+            return Err(invalid_data(
+                "The cache was saved with other masked squares.",
+            ));
+        }
+        Ok(Self {
+            visited: BoundedCache::decode(&mut reader)?,
+            ..Default::default()
+        })
+    }
+
     pub fn evaluate<const N: usize>(
         &mut self,
         state: &MaskedTicTacToe<N>,
         action: &Action,
     ) -> (i8, i8) {
+        self.tracker.enter();
+        let eval = self.search(state, action);
+        self.tracker.exit();
+        eval
+    }
+
+    /// Returns the statistics of the work done since the last call, and starts counting again.
+    pub fn take_stats(&mut self) -> SearchStats {
+        self.tracker.take(self.visited.len())
+    }
+
+    /// The body of evaluate, which recurses through evaluate so every node is counted.
+    fn search<const N: usize>(&mut self, state: &MaskedTicTacToe<N>, action: &Action) -> (i8, i8) {
         // If we were to use apply to compute the outcome of the action
         // then we would be cheating! It is not clear whether we know exactly what state we
         // are in at the moment because some moves are masked. Thus, if an outcome were to be
@@ -381,9 +522,11 @@ impl MaskedEvaluator {
         }
         // We have to do this cause otherwise the if let takes ownership of history.
         let history_tuple = (history, *action);
-        if let Some(&eval) = self.visited.get(&history_tuple) {
+        if let Some(eval) = self.visited.get(&history_tuple) {
+            self.tracker.stats.cache_hits += 1;
             return eval;
         }
+        self.tracker.stats.cache_misses += 1;
         // move back out the tuple
         let history = history_tuple.0;
         // This computes all of the potential current states we could be in given the history of
@@ -494,17 +637,23 @@ impl<const N: usize> Evaluator<MaskedTicTacToe<N>> for MaskedEvaluator {
     fn evaluate(&mut self, state: &MaskedTicTacToe<N>, action: &Action) -> Self::Evaluation {
         self.evaluate(state, action)
     }
+
+    fn take_stats(&mut self) -> SearchStats {
+        self.take_stats()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io;
+
     use crate::{
-        evaluator::Evaluator,
-        game_state::{outcome::WinDraw, player::TwoPlayer},
+        game_state::{outcome::WinDraw, player::TwoPlayer, Determinize},
         games::{
             masked_tic_tac_toe::{MaskedEvaluator, MaskedTicTacToe},
             tic_tac_toe::{Action, ALL_ACTIONS},
         },
+        rng::Rng,
     };
 
     static MASKED: [Action; 2] = [ALL_ACTIONS[0], ALL_ACTIONS[1]];
@@ -569,4 +718,60 @@ mod tests {
 
         dbg!(&game);
     }
+
+    #[test]
+    fn test_determinize_is_consistent() {
+        let mut game = MaskedTicTacToe::new([ALL_ACTIONS[0], ALL_ACTIONS[1], ALL_ACTIONS[4]]);
+        for action in [0, 4, 1, 8, 6] {
+            game.apply_unchecked_mut(&ALL_ACTIONS[action]);
+        }
+        let legal: Vec<_> = game.legal_actions().copied().collect();
+        let mut rng = Rng::new(7);
+        for _ in 0..50 {
+            let sample = game.determinize(&mut rng);
+            assert_eq!(sample.visible_history(), game.visible_history());
+            assert_eq!(sample.legal_actions().copied().collect::<Vec<_>>(), legal);
+            assert_eq!(sample.outcome(), None);
+        }
+    }
+
+    #[test]
+    fn save_and_load_the_cache() {
+        let mut game = MaskedTicTacToe::new(MASKED);
+        for action in [0, 4, 1, 8] {
+            game.apply_unchecked_mut(&ALL_ACTIONS[action]);
+        }
+        let legal: Vec<_> = game.legal_actions().copied().collect();
+        let mut evaluator = MaskedEvaluator::new();
+        let evaluations: Vec<_> = legal
+            .iter()
+            .map(|action| evaluator.evaluate(&game, action))
+            .collect();
+        let path = std::env::temp_dir().join(format!("reinfors_masked_{}.bin", std::process::id()));
+        evaluator.save(&path, &game).unwrap();
+        let mut loaded = MaskedEvaluator::load(&path, &game.genesis()).unwrap();
+        // The order of the masked squares doesn't change the game.
+        let swapped = MaskedTicTacToe::new([MASKED[1], MASKED[0]]);
+        assert!(MaskedEvaluator::load(&path, &swapped).is_ok());
+        // A game with other masked squares would need other evaluations.
+        let other = MaskedEvaluator::load(&path, &MaskedTicTacToe::new([ALL_ACTIONS[0]]));
+        assert_eq!(other.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let other = MaskedTicTacToe::new([ALL_ACTIONS[0], ALL_ACTIONS[2]]);
+        let other = MaskedEvaluator::load(&path, &other);
+        assert_eq!(other.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.visited.len(), evaluator.visited.len());
+        let reloaded: Vec<_> = legal
+            .iter()
+            .map(|action| loaded.evaluate(&game, action))
+            .collect();
+        assert_eq!(reloaded, evaluations);
+        assert_eq!(loaded.take_stats().cache_misses, 0);
+        // A small cache gives the same evaluations.
+        let mut bounded = MaskedEvaluator::with_capacity(8);
+        for (action, evaluation) in legal.iter().zip(&evaluations) {
+            assert_eq!(bounded.evaluate(&game, action), *evaluation);
+        }
+        assert!(bounded.visited.len() <= 8);
+    }
 }
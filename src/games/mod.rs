@@ -1,3 +1,5 @@
-//pub mod connect4;
+pub mod connect4;
+pub mod kuhn_poker;
+pub mod leduc_holdem;
 pub mod masked_tic_tac_toe;
 pub mod tic_tac_toe;
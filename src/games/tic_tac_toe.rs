@@ -1,12 +1,14 @@
+use crate::cache::{invalid_data, Encode};
 use crate::game_state::{
     outcome::WinDraw::{self, *},
     player::TwoPlayer,
     ApplyResult::{self, *},
-    EnumerableActions, GameState, Interactive,
+    Determinize, EnumerableActions, Features, GameState, Interactive,
 };
+use crate::rng::Rng;
 use std::{
     fmt::{Debug, Display},
-    io::{self, BufRead},
+    io::{self, BufRead, Read, Write},
 };
 
 /// We will encode positions using a bitboard. Square 0 is the lower right position on the board
@@ -242,11 +244,46 @@ impl GameState for TicTacToe {
 }
 
 impl EnumerableActions for TicTacToe {
+    const N_ACTIONS: usize = 9;
+
     fn action_index(&self, action: &Self::Action) -> usize {
         action.0.ilog2() as usize
     }
 }
 
+impl Features for TicTacToe {
+    /// A bias, the squares of the current player, the squares of the opponent, and the number of
+    /// lines holding two and one of the pieces of only the current player or only the opponent.
+    const N_FEATURES: usize = 1 + 9 + 9 + 4;
+
+    fn features(&self) -> Vec<f64> {
+        let mine = self.board[self.current_player.index()];
+        let theirs = self.board[self.current_player.last().index()];
+        let mut features = Vec::with_capacity(Self::N_FEATURES);
+        features.push(1.0);
+        features.extend((0..9).map(|i| ((mine >> i) & 1) as f64));
+        features.extend((0..9).map(|i| ((theirs >> i) & 1) as f64));
+        let mut lines = [0.0; 4];
+        for &line in &WINNING_POSITIONS {
+            match ((mine & line).count_ones(), (theirs & line).count_ones()) {
+                (2, 0) => lines[0] += 1.0,
+                (0, 2) => lines[1] += 1.0,
+                (1, 0) => lines[2] += 1.0,
+                (0, 1) => lines[3] += 1.0,
+                _ => (),
+            }
+        }
+        features.extend(lines);
+        features
+    }
+}
+
+impl Determinize for TicTacToe {
+    fn determinize(&self, _rng: &mut Rng) -> Self {
+        *self
+    }
+}
+
 impl Interactive for TicTacToe {
     fn get_user_input(&self) -> Self::Action {
         let stdin = io::stdin();
@@ -269,6 +306,53 @@ impl Interactive for TicTacToe {
     }
 }
 
+/// The index of the square, as one byte.
+impl Encode for Action {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.0.trailing_zeros() as u8).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let square = u8::decode(reader)?;
+        if square > 8 {
+            return Err(invalid_data("Not a TicTacToe square."));
+        }
+        Ok(Action(1 << square))
+    }
+}
+
+/// Both boards, then the current player and the piece of Player 1 as one byte each.
+impl Encode for TicTacToe {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.board[0].encode(writer)?;
+        self.board[1].encode(writer)?;
+        (self.current_player.index() as u8).encode(writer)?;
+        (self.player1_piece as u8).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let board = [Board::decode(reader)?, Board::decode(reader)?];
+        if (board[0] | board[1]) & !FULL != 0 || board[0] & board[1] != 0 {
+            return Err(invalid_data("Not a TicTacToe board."));
+        }
+        let current_player = match u8::decode(reader)? {
+            0 => TwoPlayer::new(true),
+            1 => TwoPlayer::new(false),
+            _ => return Err(invalid_data("Not a TwoPlayer.")),
+        };
+        let player1_piece = match u8::decode(reader)? {
+            0 => Piece::X,
+            1 => Piece::O,
+            _ => return Err(invalid_data("Player 1 must play X or O.")),
+        };
+        Ok(Self {
+            board,
+            current_player,
+            player1_piece,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::games::tic_tac_toe::*;
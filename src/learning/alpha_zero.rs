@@ -0,0 +1,773 @@
+use std::{collections::VecDeque, io, marker::PhantomData, path::PathBuf};
+
+use crate::{
+    evaluator::Evaluator,
+    game_state::{
+        outcome::Payoff, player::TwoPlayer, ApplyResult::*, EnumerableActions, Features, GameState,
+    },
+    nn::{Adam, Optimizer, PolicyValueNet},
+    rng::Rng,
+    search::{Budget, SearchStats},
+    strategy::Strategy,
+};
+
+/// A PolicyValueNet reading the Features of a state and giving a prior for every action index and
+/// the value of the state for the player to move.
+///
+/// As an Evaluator, an action is worth the payoff if it ends the game, and otherwise the negated
+/// value of the resulting state, so GreedyStrategy plays a one ply lookahead on the value head.
+/// MctsStrategy searches with both heads instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkEvaluator {
+    pub net: PolicyValueNet,
+}
+
+impl NetworkEvaluator {
+    pub fn new(net: PolicyValueNet) -> Self {
+        Self { net }
+    }
+
+    /// The prior probability of each legal action, in the order of legal_actions, and the value
+    /// of the state for the player to move.
+    pub fn predict<G>(&self, state: &G) -> (Vec<f64>, f64)
+    where
+        G: Features + EnumerableActions,
+    {
+        let (probabilities, value) = self.net.predict(&state.features(), &legal_mask(state));
+        let priors = state
+            .legal_actions()
+            .map(|action| probabilities[state.action_index(action)])
+            .collect();
+        (priors, value)
+    }
+
+    pub fn value<G>(&self, state: &G) -> f64
+    where
+        G: Features + EnumerableActions,
+    {
+        self.predict(state).1
+    }
+}
+
+impl<G> Evaluator<G> for NetworkEvaluator
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions,
+{
+    type Evaluation = f64;
+
+    fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation {
+        match state.apply(action) {
+            Finished(_, outcome) => outcome.payoff(&state.current_player()),
+            Ongoing(next_state) => -self.value(&next_state),
+        }
+    }
+}
+
+/// Returns true at the index of every legal action.
+fn legal_mask<G>(state: &G) -> Vec<bool>
+where
+    G: EnumerableActions,
+{
+    let mut mask = vec![false; G::N_ACTIONS];
+    for action in state.legal_actions() {
+        mask[state.action_index(action)] = true;
+    }
+    mask
+}
+
+struct Edge<A> {
+    action: A,
+    prior: f64,
+    visits: u32,
+    /// The sum of the values backed up through this edge, for the player taking the action.
+    value_sum: f64,
+    child: Option<usize>,
+    /// The payoff of the player taking the action, if it ends the game.
+    payoff: Option<f64>,
+}
+
+struct Node<G>
+where
+    G: GameState,
+{
+    state: G,
+    visits: u32,
+    edges: Vec<Edge<G::Action>>,
+}
+
+/// Monte Carlo tree search guided by a NetworkEvaluator, as in AlphaZero. Leaves are valued by the
+/// value head instead of rollouts, and actions are selected by PUCT: the mean value of the action
+/// plus exploration * prior * sqrt(visits of the parent) / (1 + visits of the action).
+///
+/// As a Strategy it plays the most visited action at the root.
+///
+/// The search runs until its Budget runs out, counting simulations as nodes, and always runs at
+/// least one simulation. As a Strategy it reports the simulations it ran as nodes and the depth
+/// of its deepest simulation.
+#[derive(Debug, Clone)]
+pub struct MctsStrategy {
+    pub budget: Budget,
+    pub exploration: f64,
+    /// The weight of Dirichlet noise mixed into the priors at the root, to explore in self-play.
+    pub root_noise: f64,
+    /// The concentration of every action in the Dirichlet noise.
+    pub dirichlet_alpha: f64,
+    rng: Rng,
+    stats: SearchStats,
+}
+
+impl MctsStrategy {
+    /// A search without root noise.
+    pub fn new(simulations: usize) -> Self {
+        Self::with_budget(Budget::nodes(simulations as u64))
+    }
+
+    /// A search without root noise.
+    pub fn with_budget(budget: Budget) -> Self {
+        Self {
+            budget,
+            ..Self::with_root_noise(0, 0.0, 0.3, 0)
+        }
+    }
+
+    /// A search that mixes root_noise of Dirichlet(dirichlet_alpha) noise into the priors at
+    /// the root, drawn from an Rng with the given seed.
+    pub fn with_root_noise(
+        simulations: usize,
+        root_noise: f64,
+        dirichlet_alpha: f64,
+        seed: u64,
+    ) -> Self {
+        Self {
+            budget: Budget::nodes(simulations as u64),
+            exploration: 1.5,
+            root_noise,
+            dirichlet_alpha,
+            rng: Rng::new(seed),
+            stats: SearchStats::new(),
+        }
+    }
+
+    /// Searches from state, which must not be over, and returns the number of visits of each
+    /// legal action, in the order of legal_actions.
+    pub fn search<G>(&mut self, state: &G, evaluator: &NetworkEvaluator) -> Vec<(G::Action, u32)>
+    where
+        G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions + Clone,
+        G::Action: Clone,
+    {
+        let (mut root, _) = expand(state.clone(), evaluator);
+        assert!(
+            !root.edges.is_empty(),
+            "Game isn't over but there were no legal moves available."
+        );
+        if self.root_noise > 0.0 {
+            let noise = self
+                .rng
+                .gen_dirichlet(&vec![self.dirichlet_alpha; root.edges.len()]);
+            for (edge, noise) in root.edges.iter_mut().zip(noise) {
+                edge.prior = (1.0 - self.root_noise) * edge.prior + self.root_noise * noise;
+            }
+        }
+        let mut nodes = vec![root];
+        let budget = self.budget.clone();
+        let mut limiter = budget.start();
+        let mut max_depth = 0;
+        loop {
+            max_depth = max_depth.max(self.simulate(&mut nodes, evaluator));
+            limiter.count();
+            if limiter.is_exhausted() {
+                break;
+            }
+        }
+        self.stats.merge(&SearchStats {
+            nodes: limiter.nodes(),
+            max_depth,
+            elapsed: limiter.elapsed(),
+            ..SearchStats::new()
+        });
+        nodes
+            .swap_remove(0)
+            .edges
+            .into_iter()
+            .map(|edge| (edge.action, edge.visits))
+            .collect()
+    }
+
+    /// Runs one simulation from the root and returns the number of actions it took.
+    fn simulate<G>(&self, nodes: &mut Vec<Node<G>>, evaluator: &NetworkEvaluator) -> usize
+    where
+        G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions + Clone,
+        G::Action: Clone,
+    {
+        let mut path = vec![];
+        let mut node = 0;
+        // The value of the leaf and the player it is for.
+        let (value, player) = loop {
+            let edge_index = self.select(&nodes[node]);
+            path.push((node, edge_index));
+            let mover = nodes[node].state.current_player();
+            let edge = &nodes[node].edges[edge_index];
+            if let Some(payoff) = edge.payoff {
+                break (payoff, mover);
+            }
+            if let Some(child) = edge.child {
+                node = child;
+                continue;
+            }
+            match nodes[node].state.apply(&edge.action) {
+                Finished(_, outcome) => {
+                    let payoff = outcome.payoff(&mover);
+                    nodes[node].edges[edge_index].payoff = Some(payoff);
+                    break (payoff, mover);
+                }
+                Ongoing(next_state) => {
+                    let (child, value) = expand(next_state, evaluator);
+                    let player = child.state.current_player();
+                    nodes.push(child);
+                    nodes[node].edges[edge_index].child = Some(nodes.len() - 1);
+                    break (value, player);
+                }
+            }
+        };
+        let depth = path.len();
+        for (node, edge_index) in path {
+            let node = &mut nodes[node];
+            let value = if node.state.current_player() == player {
+                value
+            } else {
+                -value
+            };
+            node.visits += 1;
+            let edge = &mut node.edges[edge_index];
+            edge.visits += 1;
+            edge.value_sum += value;
+        }
+        depth
+    }
+
+    fn select<G>(&self, node: &Node<G>) -> usize
+    where
+        G: GameState,
+    {
+        let scale = self.exploration * (node.visits.max(1) as f64).sqrt();
+        let score = |edge: &Edge<G::Action>| {
+            let mean = if edge.visits == 0 {
+                0.0
+            } else {
+                edge.value_sum / edge.visits as f64
+            };
+            mean + scale * edge.prior / (1 + edge.visits) as f64
+        };
+        let mut best = 0;
+        for (index, edge) in node.edges.iter().enumerate().skip(1) {
+            if score(edge) > score(&node.edges[best]) {
+                best = index;
+            }
+        }
+        best
+    }
+}
+
+fn expand<G>(state: G, evaluator: &NetworkEvaluator) -> (Node<G>, f64)
+where
+    G: Features + EnumerableActions,
+    G::Action: Clone,
+{
+    let (priors, value) = evaluator.predict(&state);
+    let edges = state
+        .legal_actions()
+        .zip(priors)
+        .map(|(action, prior)| Edge {
+            action: action.clone(),
+            prior,
+            visits: 0,
+            value_sum: 0.0,
+            child: None,
+            payoff: None,
+        })
+        .collect();
+    let node = Node {
+        state,
+        visits: 0,
+        edges,
+    };
+    (node, value)
+}
+
+impl<G> Strategy<G, NetworkEvaluator> for MctsStrategy
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions + Clone,
+    G::Action: Clone,
+{
+    fn choose(&mut self, state: &G, evaluator: &mut NetworkEvaluator) -> G::Action {
+        let visits = self.search(state, evaluator);
+        let best = most_visited(&visits);
+        visits[best].0.clone()
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+/// The index of the most visited action, the first one if there is a tie.
+fn most_visited<A>(visits: &[(A, u32)]) -> usize {
+    let mut best = 0;
+    for (index, (_, count)) in visits.iter().enumerate() {
+        if *count > visits[best].1 {
+            best = index;
+        }
+    }
+    best
+}
+
+/// A position from self-play: the Features of the state, the legal action indices, the share of
+/// the root visits of each action index, and the final payoff of the player to move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub observation: Vec<f64>,
+    pub mask: Vec<bool>,
+    pub policy: Vec<f64>,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaZeroConfig {
+    /// The sizes of the hidden layers of the network.
+    pub hidden: Vec<usize>,
+    pub simulations: usize,
+    pub exploration: f64,
+    pub root_noise: f64,
+    pub dirichlet_alpha: f64,
+    /// Actions are sampled in proportion to their visits for this many plies of every game,
+    /// and the most visited action is played afterwards.
+    pub temperature_plies: usize,
+    pub games_per_iteration: usize,
+    /// The number of most recent samples that training draws from.
+    pub window: usize,
+    pub training_steps: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    /// The number of games between the trained and the best network in each iteration.
+    pub arena_games: usize,
+    /// The score, counting draws as half a win, that the trained network needs to become the best.
+    pub gate_threshold: f64,
+    /// If set, the best network is written to best.net and the trained network of every
+    /// iteration to iteration_N.net in this directory.
+    pub checkpoint_dir: Option<PathBuf>,
+    pub seed: u64,
+}
+
+impl Default for AlphaZeroConfig {
+    fn default() -> Self {
+        Self {
+            hidden: vec![64, 64],
+            simulations: 50,
+            exploration: 1.5,
+            root_noise: 0.25,
+            dirichlet_alpha: 0.3,
+            temperature_plies: 4,
+            games_per_iteration: 50,
+            window: 20_000,
+            training_steps: 200,
+            batch_size: 32,
+            learning_rate: 1e-3,
+            arena_games: 20,
+            gate_threshold: 0.55,
+            checkpoint_dir: None,
+            seed: 0,
+        }
+    }
+}
+
+/// What happened in one iteration of AlphaZero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationReport {
+    pub iteration: usize,
+    pub samples: usize,
+    /// The mean loss over the last training step.
+    pub loss: f64,
+    /// The score of the trained network against the best one.
+    pub arena_score: f64,
+    pub accepted: bool,
+}
+
+/// Trains a PolicyValueNet by AlphaZero: the best network plays itself with MctsStrategy, a
+/// candidate network is trained on the visit counts and final payoffs of those games, and it
+/// replaces the best network if it scores well enough against it in an arena.
+pub struct AlphaZero<G> {
+    config: AlphaZeroConfig,
+    best: NetworkEvaluator,
+    candidate: NetworkEvaluator,
+    optimizer: Adam,
+    samples: VecDeque<Sample>,
+    iterations: usize,
+    rng: Rng,
+    game: PhantomData<G>,
+}
+
+impl<G> AlphaZero<G>
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions + Clone,
+    G::Action: Clone,
+{
+    pub fn new(config: AlphaZeroConfig) -> Self {
+        let net = PolicyValueNet::new(G::N_FEATURES, &config.hidden, G::N_ACTIONS, config.seed);
+        let optimizer = Adam::new(config.learning_rate);
+        let rng = Rng::new(config.seed);
+        Self {
+            config,
+            best: NetworkEvaluator::new(net.clone()),
+            candidate: NetworkEvaluator::new(net),
+            optimizer,
+            samples: VecDeque::new(),
+            iterations: 0,
+            rng,
+            game: PhantomData,
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The network that won the most recent arena.
+    pub fn best(&self) -> &NetworkEvaluator {
+        &self.best
+    }
+
+    pub fn into_best(self) -> NetworkEvaluator {
+        self.best
+    }
+
+    /// Runs the given number of iterations from root, stopping at the first error while writing a
+    /// checkpoint.
+    pub fn train(&mut self, root: &G, iterations: usize) -> io::Result<Vec<IterationReport>> {
+        (0..iterations).map(|_| self.iterate(root)).collect()
+    }
+
+    /// Plays games_per_iteration games of self-play, trains the candidate, gates it against the
+    /// best network and writes the checkpoints.
+    pub fn iterate(&mut self, root: &G) -> io::Result<IterationReport> {
+        for _ in 0..self.config.games_per_iteration {
+            let samples = self.self_play(root);
+            self.samples.extend(samples);
+        }
+        while self.samples.len() > self.config.window {
+            self.samples.pop_front();
+        }
+
+        let mut loss = 0.0;
+        for _ in 0..self.config.training_steps {
+            loss = self.training_step();
+        }
+
+        let arena_score = self.arena(root);
+        let accepted = arena_score >= self.config.gate_threshold;
+        if accepted {
+            self.best = self.candidate.clone();
+        }
+        self.iterations += 1;
+
+        if let Some(dir) = &self.config.checkpoint_dir {
+            std::fs::create_dir_all(dir)?;
+            self.best.net.save(dir.join("best.net"))?;
+            self.candidate
+                .net
+                .save(dir.join(format!("iteration_{}.net", self.iterations)))?;
+        }
+
+        Ok(IterationReport {
+            iteration: self.iterations,
+            samples: self.samples.len(),
+            loss,
+            arena_score,
+            accepted,
+        })
+    }
+
+    fn self_play(&mut self, root: &G) -> Vec<Sample> {
+        let mut mcts = MctsStrategy {
+            exploration: self.config.exploration,
+            ..MctsStrategy::with_root_noise(
+                self.config.simulations,
+                self.config.root_noise,
+                self.config.dirichlet_alpha,
+                self.rng.next_u64(),
+            )
+        };
+        let mut positions = vec![];
+        let mut state = root.clone();
+        let outcome = loop {
+            let visits = mcts.search(&state, &self.best);
+            let total: u32 = visits.iter().map(|(_, count)| count).sum();
+            let mut policy = vec![0.0; G::N_ACTIONS];
+            for (action, count) in &visits {
+                policy[state.action_index(action)] = *count as f64 / total as f64;
+            }
+            positions.push((
+                state.features(),
+                legal_mask(&state),
+                policy,
+                state.current_player(),
+            ));
+            let chosen = self.pick(&visits, positions.len() - 1);
+            match state.apply(&visits[chosen].0) {
+                Ongoing(next_state) => state = next_state,
+                Finished(_, outcome) => break outcome,
+            }
+        };
+        positions
+            .into_iter()
+            .map(|(observation, mask, policy, player)| Sample {
+                observation,
+                mask,
+                policy,
+                value: outcome.payoff(&player),
+            })
+            .collect()
+    }
+
+    /// Samples an action in proportion to its visits in the opening, and plays the most
+    /// visited one afterwards.
+    fn pick(&mut self, visits: &[(G::Action, u32)], ply: usize) -> usize {
+        if ply >= self.config.temperature_plies {
+            return most_visited(visits);
+        }
+        let weights: Vec<f64> = visits.iter().map(|(_, count)| *count as f64).collect();
+        self.rng.gen_weighted_index(&weights)
+    }
+
+    /// Takes one Adam step on a minibatch drawn uniformly from the window, and returns its mean
+    /// loss.
+    fn training_step(&mut self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let net = &mut self.candidate.net;
+        let mut grads = vec![0.0; net.params.len()];
+        let mut loss = 0.0;
+        for _ in 0..self.config.batch_size {
+            let sample = &self.samples[self.rng.gen_index(self.samples.len())];
+            loss += net.backward(
+                &sample.observation,
+                &sample.mask,
+                &sample.policy,
+                sample.value,
+                &mut grads,
+            );
+        }
+        let batch_size = self.config.batch_size as f64;
+        for grad in &mut grads {
+            *grad /= batch_size;
+        }
+        self.optimizer.step(&mut net.params, &grads);
+        loss / batch_size
+    }
+
+    /// Plays the candidate against the best network, alternating seats, and returns the score
+    /// of the candidate.
+    fn arena(&mut self, root: &G) -> f64 {
+        if self.config.arena_games == 0 {
+            return 1.0;
+        }
+        let mut mcts = MctsStrategy {
+            exploration: self.config.exploration,
+            ..MctsStrategy::new(self.config.simulations)
+        };
+        let mut score = 0.0;
+        for game in 0..self.config.arena_games {
+            let candidate_seat = TwoPlayer::new(game.is_multiple_of(2));
+            let mut state = root.clone();
+            let mut ply = 0;
+            let outcome = loop {
+                let evaluator = if state.current_player() == candidate_seat {
+                    &self.candidate
+                } else {
+                    &self.best
+                };
+                let visits = mcts.search(&state, evaluator);
+                let chosen = self.pick(&visits, ply);
+                ply += 1;
+                match state.apply(&visits[chosen].0) {
+                    Ongoing(next_state) => state = next_state,
+                    Finished(_, outcome) => break outcome,
+                }
+            };
+            // Map the payoff in [-1, 1] to a score in [0, 1].
+            score += (outcome.payoff(&candidate_seat) + 1.0) / 2.0;
+        }
+        score / self.config.arena_games as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_player::{Agent, GamePlayer, Match, StrategyAgent},
+        game_state::{outcome::WinDraw::*, player::TwoPlayer, EnumerableActions, Features},
+        games::{
+            connect4::Connect4,
+            tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        },
+        learning::alpha_zero::{AlphaZero, AlphaZeroConfig, MctsStrategy, NetworkEvaluator},
+        nn::PolicyValueNet,
+        search::Budget,
+        strategy::{GreedyStrategy, Strategy},
+    };
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    fn small_config() -> AlphaZeroConfig {
+        AlphaZeroConfig {
+            hidden: vec![32],
+            simulations: 30,
+            games_per_iteration: 10,
+            training_steps: 50,
+            arena_games: 4,
+            seed: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn search_finds_the_win() {
+        let net = PolicyValueNet::new(TicTacToe::N_FEATURES, &[16], TicTacToe::N_ACTIONS, 1);
+        let mut evaluator = NetworkEvaluator::new(net);
+        let action = MctsStrategy::new(100).choose(&position(), &mut evaluator);
+        assert_eq!(action, ALL_ACTIONS[2]);
+    }
+
+    #[test]
+    fn budgets_bound_the_search() {
+        let net = PolicyValueNet::new(TicTacToe::N_FEATURES, &[16], TicTacToe::N_ACTIONS, 1);
+        let evaluator = NetworkEvaluator::new(net);
+        let visits = |budget| {
+            let visits = MctsStrategy::with_budget(budget).search(&position(), &evaluator);
+            visits.iter().map(|(_, count)| count).sum::<u32>()
+        };
+        assert_eq!(visits(Budget::nodes(40)), 40);
+        // A zero time budget, or an empty one, still runs one simulation.
+        assert_eq!(visits(Budget::time(Duration::ZERO)), 1);
+        assert_eq!(visits(Budget::nodes(0)), 1);
+    }
+
+    #[test]
+    fn reports_its_simulations() {
+        let net = PolicyValueNet::new(TicTacToe::N_FEATURES, &[16], TicTacToe::N_ACTIONS, 1);
+        let mut evaluator = NetworkEvaluator::new(net);
+        let mut strategy = MctsStrategy::new(40);
+        strategy.choose(&position(), &mut evaluator);
+        strategy.choose(&position(), &mut evaluator);
+        let stats = Strategy::<TicTacToe, NetworkEvaluator>::take_stats(&mut strategy);
+        assert_eq!(stats.nodes, 80);
+        assert!(stats.max_depth >= 2);
+        let stats = Strategy::<TicTacToe, NetworkEvaluator>::take_stats(&mut strategy);
+        assert_eq!(stats.nodes, 0);
+        let mut hurried = MctsStrategy::with_budget(Budget::nodes(0));
+        hurried.choose(&position(), &mut evaluator);
+        let stats = Strategy::<TicTacToe, NetworkEvaluator>::take_stats(&mut hurried);
+        assert_eq!(stats.nodes, 1);
+    }
+
+    #[test]
+    fn learns_the_winning_move_and_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("reinfors_alpha_zero_{}", std::process::id()));
+        let mut alpha_zero = AlphaZero::new(AlphaZeroConfig {
+            checkpoint_dir: Some(dir.clone()),
+            ..small_config()
+        });
+        let reports = alpha_zero.train(&position(), 3).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports.iter().all(|report| report.loss.is_finite()));
+        let best = alpha_zero.into_best();
+        assert_eq!(
+            PolicyValueNet::load(dir.join("best.net")).unwrap(),
+            best.net
+        );
+        assert!(dir.join("iteration_3.net").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (priors, value) = best.predict(&position());
+        let winning = priors.iter().cloned().fold(0.0, f64::max);
+        assert_eq!(priors[0], winning, "{:?}", priors);
+        assert!(value > 0.0);
+    }
+
+    #[test]
+    fn plays_connect4() {
+        let mut alpha_zero = AlphaZero::new(AlphaZeroConfig {
+            simulations: 10,
+            games_per_iteration: 1,
+            training_steps: 5,
+            arena_games: 0,
+            ..small_config()
+        });
+        let report = alpha_zero.iterate(&Connect4::new()).unwrap();
+        assert!(report.accepted && report.samples > 0);
+        let mut game_player = GamePlayer::new(
+            Connect4::new(),
+            alpha_zero.into_best(),
+            MctsStrategy::new(10),
+        );
+        game_player.play();
+    }
+
+    /// Plays 20 games between agent and other, alternating seats, and returns the wins and
+    /// losses of agent.
+    fn record(
+        agent: &mut impl Agent<TicTacToe>,
+        mut other: impl Agent<TicTacToe>,
+    ) -> (usize, usize) {
+        let (mut wins, mut losses) = (0, 0);
+        for game in 0..20usize {
+            let seat = TwoPlayer::new(game.is_multiple_of(2));
+            let root = TicTacToe::new(Piece::X);
+            let mut game_match = if seat.index() == 0 {
+                Match::new(root, &mut *agent, &mut other)
+            } else {
+                Match::new(root, &mut other, &mut *agent)
+            };
+            match game_match.play().1 {
+                Win(winner) if winner == seat => wins += 1,
+                Win(_) => losses += 1,
+                Draw => (),
+            }
+        }
+        (wins, losses)
+    }
+
+    #[test]
+    fn learns_tic_tac_toe_from_the_root() {
+        let mut alpha_zero = AlphaZero::new(AlphaZeroConfig {
+            hidden: vec![64],
+            games_per_iteration: 30,
+            training_steps: 100,
+            ..small_config()
+        });
+        alpha_zero.train(&TicTacToe::new(Piece::X), 10).unwrap();
+        // With only 5 simulations per move the search is too shallow to play well on its own,
+        // so the strength comes from the network.
+        let mut agent = StrategyAgent::new(alpha_zero.into_best(), MctsStrategy::new(5));
+        let (_, losses) = record(
+            &mut agent,
+            StrategyAgent::new(MinimaxEvaluator::new(), GreedyStrategy),
+        );
+        assert_eq!(losses, 0);
+        let (wins, _) = record(
+            &mut agent,
+            StrategyAgent::new(RandomEvaluator::new(4), GreedyStrategy),
+        );
+        assert!(wins >= 15, "{}", wins);
+    }
+}
@@ -0,0 +1,42 @@
+pub mod alpha_zero;
+pub mod policy_gradient;
+pub mod q_learning;
+pub mod replay;
+pub mod td_lambda;
+
+/// A hyperparameter that changes over the course of training, e.g. a learning rate or an
+/// exploration rate. The step is usually the number of episodes played so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    Constant(f64),
+    /// Moves linearly from start to end over the given number of steps, then stays at end.
+    Linear {
+        start: f64,
+        end: f64,
+        steps: usize,
+    },
+    /// start * decay^step, but never less than min.
+    Exponential {
+        start: f64,
+        decay: f64,
+        min: f64,
+    },
+}
+
+impl Schedule {
+    pub fn value(&self, step: usize) -> f64 {
+        match *self {
+            Schedule::Constant(value) => value,
+            Schedule::Linear { start, end, steps } => {
+                if step >= steps {
+                    end
+                } else {
+                    start + (end - start) * step as f64 / steps as f64
+                }
+            }
+            Schedule::Exponential { start, decay, min } => {
+                (start * decay.powf(step as f64)).max(min)
+            }
+        }
+    }
+}
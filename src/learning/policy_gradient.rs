@@ -0,0 +1,299 @@
+use std::marker::PhantomData;
+
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, EnumerableActions, Features},
+    learning::{td_lambda::LinearValueEvaluator, Schedule},
+    policy::{self, Policy},
+    rng::Rng,
+    strategy::Strategy,
+};
+
+/// A stochastic policy that gives every action a preference, a weighted sum of the Features of
+/// the state with one weight vector per action index, and plays the legal actions with
+/// probabilities proportional to the exponentiated preferences. Illegal actions are masked out.
+///
+/// As an Evaluator, an action is worth its preference, so GreedyStrategy plays the most likely
+/// action. PolicySampler samples from the policy instead.
+#[derive(Debug, Clone)]
+pub struct LinearSoftmaxPolicy {
+    /// The weights of action index i are weights[i * n_features..(i + 1) * n_features].
+    pub weights: Vec<f64>,
+    n_features: usize,
+}
+
+impl LinearSoftmaxPolicy {
+    /// A uniform policy over the legal actions.
+    pub fn new(n_actions: usize, n_features: usize) -> Self {
+        Self {
+            weights: vec![0.0; n_actions * n_features],
+            n_features,
+        }
+    }
+
+    pub fn preference<G>(&self, state: &G, action: &G::Action) -> f64
+    where
+        G: Features + EnumerableActions,
+    {
+        self.preference_of(state.action_index(action), &state.features())
+    }
+
+    /// The probability of each legal action, in the order of legal_actions.
+    pub fn probabilities<G>(&self, state: &G) -> Vec<f64>
+    where
+        G: Features + EnumerableActions,
+    {
+        let features = state.features();
+        let indices: Vec<usize> = state
+            .legal_actions()
+            .map(|action| state.action_index(action))
+            .collect();
+        self.softmax(&indices, &features)
+    }
+
+    fn preference_of(&self, index: usize, features: &[f64]) -> f64 {
+        let start = index * self.n_features;
+        self.weights[start..start + self.n_features]
+            .iter()
+            .zip(features)
+            .map(|(weight, feature)| weight * feature)
+            .sum()
+    }
+
+    fn softmax(&self, indices: &[usize], features: &[f64]) -> Vec<f64> {
+        let preferences: Vec<f64> = indices
+            .iter()
+            .map(|&index| self.preference_of(index, features))
+            .collect();
+        // Subtracting the largest preference keeps exp from overflowing.
+        let max = preferences
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let exps: Vec<f64> = preferences.iter().map(|p| (p - max).exp()).collect();
+        let total: f64 = exps.iter().sum();
+        exps.into_iter().map(|exp| exp / total).collect()
+    }
+}
+
+impl<G> Evaluator<G> for LinearSoftmaxPolicy
+where
+    G: Features + EnumerableActions,
+{
+    type Evaluation = f64;
+
+    fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation {
+        self.preference(state, action)
+    }
+}
+
+impl<G> Policy<G> for LinearSoftmaxPolicy
+where
+    G: Features + EnumerableActions,
+{
+    fn distribution(&mut self, state: &G) -> Vec<f64> {
+        self.probabilities(state)
+    }
+}
+
+/// Plays an action sampled from the LinearSoftmaxPolicy passed as the evaluator, so that
+/// GamePlayer can play a policy it is also training. PolicyStrategy samples a policy it owns.
+#[derive(Debug, Clone)]
+pub struct PolicySampler {
+    rng: Rng,
+}
+
+impl PolicySampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G> Strategy<G, LinearSoftmaxPolicy> for PolicySampler
+where
+    G: Features + EnumerableActions,
+    G::Action: Clone,
+{
+    fn choose(&mut self, state: &G, policy: &mut LinearSoftmaxPolicy) -> G::Action {
+        policy::sample(policy, state, &mut self.rng)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReinforceConfig {
+    pub policy_learning_rate: Schedule,
+    pub baseline_learning_rate: Schedule,
+    pub discount: f64,
+    pub seed: u64,
+}
+
+impl Default for ReinforceConfig {
+    fn default() -> Self {
+        Self {
+            policy_learning_rate: Schedule::Constant(0.05),
+            baseline_learning_rate: Schedule::Constant(0.02),
+            discount: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Trains a LinearSoftmaxPolicy from self-play with REINFORCE, using a LinearValueEvaluator of
+/// the state as a learned baseline.
+///
+/// Both players sample from and update the same policy. After every episode, each action is
+/// reinforced by the discounted payoff of the player who took it minus the baseline value of the
+/// state it was taken in, and the baseline moves towards that payoff.
+#[derive(Debug)]
+pub struct Reinforce<G> {
+    config: ReinforceConfig,
+    policy: LinearSoftmaxPolicy,
+    baseline: LinearValueEvaluator,
+    episodes: usize,
+    rng: Rng,
+    game: PhantomData<G>,
+}
+
+impl<G> Reinforce<G>
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions + Clone,
+    G::Action: Clone,
+{
+    pub fn new(config: ReinforceConfig) -> Self {
+        Self {
+            config,
+            policy: LinearSoftmaxPolicy::new(G::N_ACTIONS, G::N_FEATURES),
+            baseline: LinearValueEvaluator::new(G::N_FEATURES),
+            episodes: 0,
+            rng: Rng::new(config.seed),
+            game: PhantomData,
+        }
+    }
+
+    pub fn episodes(&self) -> usize {
+        self.episodes
+    }
+
+    pub fn policy(&self) -> &LinearSoftmaxPolicy {
+        &self.policy
+    }
+
+    pub fn baseline(&self) -> &LinearValueEvaluator {
+        &self.baseline
+    }
+
+    pub fn into_policy(self) -> LinearSoftmaxPolicy {
+        self.policy
+    }
+
+    /// Plays the given number of games from root against itself.
+    pub fn train(&mut self, root: &G, episodes: usize) {
+        for _ in 0..episodes {
+            self.episode(root);
+            self.episodes += 1;
+        }
+    }
+
+    fn episode(&mut self, root: &G) {
+        // (features, legal action indices, index into the legal actions, mover)
+        let mut trajectory = vec![];
+        let mut state = root.clone();
+        let outcome = loop {
+            let features = state.features();
+            let indices: Vec<usize> = state
+                .legal_actions()
+                .map(|action| state.action_index(action))
+                .collect();
+            let probabilities = self.policy.softmax(&indices, &features);
+            let chosen = self.rng.gen_weighted_index(&probabilities);
+            let action = state
+                .legal_actions()
+                .nth(chosen)
+                .expect("Game isn't over but there were no legal moves available.")
+                .clone();
+            trajectory.push((features, indices, chosen, state.current_player()));
+            match state.apply(&action) {
+                Ongoing(next_state) => state = next_state,
+                Finished(_, outcome) => break outcome,
+            }
+        };
+
+        let policy_learning_rate = self.config.policy_learning_rate.value(self.episodes);
+        let baseline_learning_rate = self.config.baseline_learning_rate.value(self.episodes);
+        let n_features = self.policy.n_features;
+        let mut scale = 1.0;
+        for (features, indices, chosen, mover) in trajectory.into_iter().rev() {
+            let target = scale * outcome.payoff(&mover);
+            scale *= self.config.discount;
+            let advantage = target - self.baseline.value_of(&features);
+            for (weight, feature) in self.baseline.weights.iter_mut().zip(&features) {
+                *weight += baseline_learning_rate * advantage * feature;
+            }
+            // The gradient of log pi(a) with respect to the weights of legal action b is
+            // (1[a = b] - pi(b)) times the features.
+            let probabilities = self.policy.softmax(&indices, &features);
+            for (i, (&index, probability)) in indices.iter().zip(probabilities).enumerate() {
+                let indicator = if i == chosen { 1.0 } else { 0.0 };
+                let step = policy_learning_rate * advantage * (indicator - probability);
+                let start = index * n_features;
+                for (weight, feature) in self.policy.weights[start..start + n_features]
+                    .iter_mut()
+                    .zip(&features)
+                {
+                    *weight += step * feature;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game_player::GamePlayer,
+        game_state::GameState,
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        learning::policy_gradient::{PolicySampler, Reinforce, ReinforceConfig},
+        strategy::{GreedyStrategy, Strategy},
+    };
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    #[test]
+    fn self_play_learns_to_win() {
+        let mut learner = Reinforce::new(ReinforceConfig {
+            seed: 5,
+            ..Default::default()
+        });
+        let before = learner.policy().probabilities(&position());
+        assert!(before.iter().all(|&p| (p - 0.2).abs() < 1e-12));
+        learner.train(&position(), 2000);
+        let mut policy = learner.into_policy();
+        let probabilities = policy.probabilities(&position());
+        assert_eq!(probabilities.len(), position().legal_actions().count());
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(probabilities[0] > 0.5, "{:?}", probabilities);
+        let action = GreedyStrategy.choose(&position(), &mut policy);
+        assert_eq!(action, ALL_ACTIONS[2]);
+    }
+
+    #[test]
+    fn sampled_policy_plays_legal_games() {
+        let root = TicTacToe::new(Piece::X);
+        let mut learner = Reinforce::new(ReinforceConfig::default());
+        learner.train(&root, 200);
+        let policy = learner.into_policy();
+        assert!(policy.weights.iter().all(|weight| weight.is_finite()));
+        let mut game_player = GamePlayer::new(root, policy, PolicySampler::new(1));
+        game_player.play();
+    }
+}
@@ -0,0 +1,392 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, GameState},
+    learning::Schedule,
+    rng::Rng,
+    strategy::Strategy,
+};
+
+/// An Evaluator that looks up the learned value of playing an action in a state. The value is
+/// from the perspective of the player to move, so GreedyStrategy plays the best learned action.
+/// Pairs that were never visited are worth 0.
+#[derive(Debug, Clone)]
+pub struct QTableEvaluator<G>
+where
+    G: GameState,
+{
+    pub table: HashMap<(G, G::Action), f64>,
+}
+
+impl<G> QTableEvaluator<G>
+where
+    G: GameState + Hash + Eq + Clone,
+    G::Action: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            table: HashMap::new(),
+        }
+    }
+
+    pub fn value(&self, state: &G, action: &G::Action) -> f64 {
+        // Cloning the key is the price of not requiring Borrow impls from every game.
+        self.table
+            .get(&(state.clone(), action.clone()))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The value of the best action in the given state, or 0 if there are no legal actions.
+    pub fn max_value(&self, state: &G) -> f64 {
+        state
+            .legal_actions()
+            .map(|action| self.value(state, action))
+            .reduce(f64::max)
+            .unwrap_or(0.0)
+    }
+
+    fn update(&mut self, state: &G, action: &G::Action, target: f64, learning_rate: f64) {
+        let value = self
+            .table
+            .entry((state.clone(), action.clone()))
+            .or_insert(0.0);
+        *value += learning_rate * (target - *value);
+    }
+}
+
+impl<G> Default for QTableEvaluator<G>
+where
+    G: GameState + Hash + Eq + Clone,
+    G::Action: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> Evaluator<G> for QTableEvaluator<G>
+where
+    G: GameState + Hash + Eq + Clone,
+    G::Action: Hash + Eq + Clone,
+{
+    type Evaluation = f64;
+
+    fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation {
+        self.value(state, action)
+    }
+}
+
+/// How the value of a state-action pair is estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateRule {
+    /// One step Q-learning: bootstrap from the best action in the next state of the learner.
+    QLearning,
+    /// Every-visit Monte Carlo control: move towards the discounted final payoff.
+    MonteCarlo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QLearningConfig {
+    pub update: UpdateRule,
+    pub learning_rate: Schedule,
+    pub discount: Schedule,
+    /// The probability of playing a uniformly random action instead of the best one.
+    pub epsilon: Schedule,
+    pub seed: u64,
+}
+
+impl Default for QLearningConfig {
+    fn default() -> Self {
+        Self {
+            update: UpdateRule::QLearning,
+            learning_rate: Schedule::Constant(0.2),
+            discount: Schedule::Constant(1.0),
+            epsilon: Schedule::Linear {
+                start: 1.0,
+                end: 0.05,
+                steps: 10_000,
+            },
+            seed: 0,
+        }
+    }
+}
+
+/// Trains a QTableEvaluator for two player games, either by self-play, where both players share
+/// and update the same table, or against a fixed opponent.
+///
+/// In self-play, values are always from the perspective of the player to move, so after an
+/// action the value of the next state is the negation of the opponent's best value (negamax).
+/// Payoffs come from the Outcome, e.g. 1, 0 and -1 for a win, draw and loss with WinDraw.
+pub struct QLearner<G>
+where
+    G: GameState,
+{
+    config: QLearningConfig,
+    q_table: QTableEvaluator<G>,
+    episodes: usize,
+    rng: Rng,
+}
+
+impl<G> QLearner<G>
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Hash + Eq + Clone,
+    G::Action: Hash + Eq + Clone,
+{
+    pub fn new(config: QLearningConfig) -> Self {
+        Self {
+            config,
+            q_table: QTableEvaluator::new(),
+            episodes: 0,
+            rng: Rng::new(config.seed),
+        }
+    }
+
+    /// The number of episodes trained so far. Schedules are evaluated at this step.
+    pub fn episodes(&self) -> usize {
+        self.episodes
+    }
+
+    pub fn evaluator(&self) -> &QTableEvaluator<G> {
+        &self.q_table
+    }
+
+    pub fn into_evaluator(self) -> QTableEvaluator<G> {
+        self.q_table
+    }
+
+    /// Plays the given number of games from root against itself.
+    pub fn train_self_play(&mut self, root: &G, episodes: usize) {
+        for _ in 0..episodes {
+            match self.config.update {
+                UpdateRule::QLearning => self.q_learning_self_play(root),
+                UpdateRule::MonteCarlo => {
+                    let mut trajectory = vec![];
+                    let mut state = root.clone();
+                    let outcome = loop {
+                        let action = self.explore(&state);
+                        let mover = state.current_player();
+                        let result = state.apply(&action);
+                        trajectory.push((state, action, mover));
+                        match result {
+                            Ongoing(next_state) => state = next_state,
+                            Finished(_, outcome) => break outcome,
+                        }
+                    };
+                    self.monte_carlo_update(trajectory, &outcome);
+                }
+            }
+            self.episodes += 1;
+        }
+    }
+
+    /// Plays the given number of games from root against a fixed opponent, which chooses its
+    /// actions with the given strategy and evaluator. The learner alternates between the seats.
+    /// Only the learner's actions are updated.
+    pub fn train_against<E, S>(
+        &mut self,
+        root: &G,
+        episodes: usize,
+        evaluator: &mut E,
+        strategy: &mut S,
+    ) where
+        E: Evaluator<G>,
+        S: Strategy<G, E>,
+    {
+        for _ in 0..episodes {
+            let learner = TwoPlayer::new(self.episodes.is_multiple_of(2));
+            let mut trajectory = vec![];
+            let mut state = root.clone();
+            let outcome = loop {
+                let mover = state.current_player();
+                let action = if mover == learner {
+                    self.explore(&state)
+                } else {
+                    strategy.choose(&state, evaluator)
+                };
+                let result = state.apply(&action);
+                if mover == learner {
+                    trajectory.push((state, action, mover));
+                }
+                match result {
+                    Ongoing(next_state) => state = next_state,
+                    Finished(_, outcome) => break outcome,
+                }
+                // Once it is the learner's turn again, the previous action can be bootstrapped.
+                if self.config.update == UpdateRule::QLearning && state.current_player() == learner
+                {
+                    if let Some((previous, action, _)) = trajectory.last() {
+                        let target = self.discount() * self.q_table.max_value(&state);
+                        let learning_rate = self.learning_rate();
+                        self.q_table.update(previous, action, target, learning_rate);
+                    }
+                }
+            };
+            match self.config.update {
+                UpdateRule::QLearning => {
+                    if let Some((previous, action, _)) = trajectory.last() {
+                        let learning_rate = self.learning_rate();
+                        self.q_table.update(
+                            previous,
+                            action,
+                            outcome.payoff(&learner),
+                            learning_rate,
+                        );
+                    }
+                }
+                UpdateRule::MonteCarlo => self.monte_carlo_update(trajectory, &outcome),
+            }
+            self.episodes += 1;
+        }
+    }
+
+    fn q_learning_self_play(&mut self, root: &G) {
+        let mut state = root.clone();
+        loop {
+            let action = self.explore(&state);
+            let learning_rate = self.learning_rate();
+            match state.apply(&action) {
+                Finished(_, outcome) => {
+                    let target = outcome.payoff(&state.current_player());
+                    self.q_table.update(&state, &action, target, learning_rate);
+                    return;
+                }
+                Ongoing(next_state) => {
+                    let target = -self.discount() * self.q_table.max_value(&next_state);
+                    self.q_table.update(&state, &action, target, learning_rate);
+                    state = next_state;
+                }
+            }
+        }
+    }
+
+    /// Moves every visited pair towards the final payoff of the player who took the action,
+    /// discounted by the number of the learner's steps until the end.
+    fn monte_carlo_update(
+        &mut self,
+        trajectory: Vec<(G, G::Action, TwoPlayer)>,
+        outcome: &G::Outcome,
+    ) {
+        let (learning_rate, discount) = (self.learning_rate(), self.discount());
+        let mut scale = 1.0;
+        for (state, action, mover) in trajectory.into_iter().rev() {
+            let target = scale * outcome.payoff(&mover);
+            self.q_table.update(&state, &action, target, learning_rate);
+            scale *= discount;
+        }
+    }
+
+    /// Epsilon-greedy: a uniformly random action with probability epsilon, otherwise one of the
+    /// actions with the highest value, breaking ties at random.
+    fn explore(&mut self, state: &G) -> G::Action {
+        let actions: Vec<&G::Action> = state.legal_actions().collect();
+        assert!(
+            !actions.is_empty(),
+            "Game isn't over but there were no legal moves available."
+        );
+        if self.rng.gen_f64() < self.config.epsilon.value(self.episodes) {
+            return (*self.rng.choose(&actions).expect("There are legal actions.")).clone();
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .map(|action| self.q_table.value(state, action))
+            .collect();
+        let best = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let best_actions: Vec<&G::Action> = actions
+            .into_iter()
+            .zip(values)
+            .filter(|(_, value)| *value == best)
+            .map(|(action, _)| action)
+            .collect();
+        (*self
+            .rng
+            .choose(&best_actions)
+            .expect("There is a best action."))
+        .clone()
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.config.learning_rate.value(self.episodes)
+    }
+
+    fn discount(&self) -> f64 {
+        self.config.discount.value(self.episodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::RandomEvaluator,
+        game_state::{outcome::WinDraw, player::TwoPlayer, ApplyResult::*, GameState},
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        learning::{
+            q_learning::{QLearner, QLearningConfig, UpdateRule},
+            Schedule,
+        },
+        strategy::{GreedyStrategy, Strategy},
+    };
+
+    fn config(update: UpdateRule) -> QLearningConfig {
+        QLearningConfig {
+            update,
+            learning_rate: Schedule::Constant(0.5),
+            epsilon: Schedule::Constant(0.3),
+            seed: 11,
+            ..Default::default()
+        }
+    }
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    #[test]
+    fn self_play_learns_to_win() {
+        for update in [UpdateRule::QLearning, UpdateRule::MonteCarlo] {
+            let mut learner = QLearner::new(config(update));
+            learner.train_self_play(&position(), 2000);
+            let mut evaluator = learner.into_evaluator();
+            let action = GreedyStrategy.choose(&position(), &mut evaluator);
+            assert_eq!(action, ALL_ACTIONS[2]);
+        }
+    }
+
+    #[test]
+    fn learns_to_beat_a_random_opponent() {
+        let mut learner = QLearner::new(QLearningConfig {
+            seed: 3,
+            ..Default::default()
+        });
+        let root = TicTacToe::new(Piece::X);
+        let mut random = RandomEvaluator::new(4);
+        learner.train_against(&root, 20_000, &mut random, &mut GreedyStrategy);
+        let mut q_table = learner.into_evaluator();
+        let mut losses = 0;
+        for game in 0..200usize {
+            let seat = TwoPlayer::new(game.is_multiple_of(2));
+            let mut state = root;
+            let outcome = loop {
+                let action = if state.current_player() == seat {
+                    GreedyStrategy.choose(&state, &mut q_table)
+                } else {
+                    GreedyStrategy.choose(&state, &mut random)
+                };
+                match GameState::apply(&state, &action) {
+                    Ongoing(next_state) => state = next_state,
+                    Finished(_, outcome) => break outcome,
+                }
+            };
+            if outcome == WinDraw::Win(seat.next()) {
+                losses += 1;
+            }
+        }
+        assert!(losses < 10, "lost {} of 200 games", losses);
+    }
+}
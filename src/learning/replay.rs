@@ -0,0 +1,537 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    cache::invalid_data,
+    game_state::{outcome::Payoff, player::TwoPlayer, EnumerableActions, Features},
+    rng::Rng,
+};
+
+/// One action taken in a game: the Features of the state it was taken in, its action index, the
+/// legal action indices, the reward of the player who took it and whether it was their last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub observation: Vec<f64>,
+    pub action: usize,
+    pub legal_mask: Vec<bool>,
+    pub reward: f64,
+    pub player: TwoPlayer,
+    pub done: bool,
+}
+
+/// The transitions of a game in the order they were played.
+///
+/// Rewards only come at the end of the game, so every transition is worth 0 except the last one
+/// of each player, which is done and holds that player's payoff. The transitions of one player
+/// therefore form an episode of their own.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Trajectory {
+    pub transitions: Vec<Transition>,
+}
+
+impl Trajectory {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Records that action is about to be played in state.
+    pub fn record<G>(&mut self, state: &G, action: &G::Action)
+    where
+        G: Features<Player = TwoPlayer> + EnumerableActions,
+    {
+        let mut legal_mask = vec![false; G::N_ACTIONS];
+        for legal in state.legal_actions() {
+            legal_mask[state.action_index(legal)] = true;
+        }
+        self.transitions.push(Transition {
+            observation: state.features(),
+            action: state.action_index(action),
+            legal_mask,
+            reward: 0.0,
+            player: state.current_player(),
+            done: false,
+        });
+    }
+
+    /// Marks the last transition of each player as done and gives it their payoff.
+    pub fn finish<O>(&mut self, outcome: &O)
+    where
+        O: Payoff<TwoPlayer>,
+    {
+        for player in [TwoPlayer::new(true), TwoPlayer::new(false)] {
+            if let Some(last) = self
+                .transitions
+                .iter_mut()
+                .rev()
+                .find(|transition| transition.player == player)
+            {
+                last.reward = outcome.payoff(&player);
+                last.done = true;
+            }
+        }
+    }
+}
+
+/// A binary tree whose leaves hold priorities and whose inner nodes hold the sum of their
+/// children, to sample in proportion to priority and update a priority in logarithmic time.
+#[derive(Debug, Clone, PartialEq)]
+struct SumTree {
+    leaves: usize,
+    nodes: Vec<f64>,
+}
+
+impl SumTree {
+    /// An empty tree, which grows as priorities are set.
+    fn new() -> Self {
+        Self {
+            leaves: 1,
+            nodes: vec![0.0; 2],
+        }
+    }
+
+    /// Doubles the number of leaves until there are at least n, keeping the priorities.
+    fn grow(&mut self, n: usize) {
+        let leaves = n.next_power_of_two();
+        let mut nodes = vec![0.0; 2 * leaves];
+        nodes[leaves..leaves + self.leaves].copy_from_slice(&self.nodes[self.leaves..]);
+        for node in (1..leaves).rev() {
+            nodes[node] = nodes[2 * node] + nodes[2 * node + 1];
+        }
+        *self = Self { leaves, nodes };
+    }
+
+    fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    fn get(&self, index: usize) -> f64 {
+        self.nodes[self.leaves + index]
+    }
+
+    fn set(&mut self, index: usize, priority: f64) {
+        if index >= self.leaves {
+            self.grow(index + 1);
+        }
+        let mut node = self.leaves + index;
+        self.nodes[node] = priority;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    /// The index of the leaf where the running sum of the priorities passes mass.
+    fn find(&self, mut mass: f64) -> usize {
+        let mut node = 1;
+        while node < self.leaves {
+            let left = 2 * node;
+            if mass < self.nodes[left] {
+                node = left;
+            } else {
+                mass -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        node - self.leaves
+    }
+}
+
+/// Stores the most recent transitions up to a capacity, evicting the oldest first, and samples
+/// them uniformly or in proportion to priority^alpha, as in prioritized experience replay.
+///
+/// New transitions get the highest priority seen so far, so they are sampled at least once
+/// before their priority is updated, usually to the magnitude of their TD error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayBuffer {
+    capacity: usize,
+    transitions: Vec<Transition>,
+    /// Where the next transition goes once the buffer is full.
+    next: usize,
+    /// The priorities raised to alpha, for sampling.
+    priorities: SumTree,
+    /// The priorities as they were set.
+    raw_priorities: Vec<f64>,
+    max_priority: f64,
+    /// How strongly priorities skew sampling, 0 being uniform. Stored priorities are already
+    /// raised to alpha, so change it before pushing any transitions.
+    pub alpha: f64,
+}
+
+impl ReplayBuffer {
+    /// A buffer with the given capacity and an alpha of 0.6.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "The capacity must be positive.");
+        Self {
+            capacity,
+            transitions: vec![],
+            next: 0,
+            priorities: SumTree::new(),
+            raw_priorities: vec![],
+            max_priority: 1.0,
+            alpha: 0.6,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &Transition {
+        &self.transitions[index]
+    }
+
+    /// The priority of the transition at index, before raising it to alpha.
+    pub fn priority(&self, index: usize) -> f64 {
+        self.raw_priorities[index]
+    }
+
+    /// The transitions from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Transition> {
+        self.indices_by_age().map(|index| &self.transitions[index])
+    }
+
+    /// The indices of the transitions from oldest to newest.
+    fn indices_by_age(&self) -> impl Iterator<Item = usize> {
+        (self.next..self.len()).chain(0..self.next)
+    }
+
+    pub fn push(&mut self, transition: Transition) {
+        self.insert(transition, self.max_priority);
+    }
+
+    pub fn extend(&mut self, trajectory: Trajectory) {
+        for transition in trajectory.transitions {
+            self.push(transition);
+        }
+    }
+
+    fn insert(&mut self, transition: Transition, priority: f64) {
+        let index = if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+            self.transitions.len() - 1
+        } else {
+            let index = self.next;
+            self.transitions[index] = transition;
+            self.next = (index + 1) % self.capacity;
+            index
+        };
+        self.set_priority(index, priority);
+    }
+
+    /// Sets the priority of the transition at index, e.g. to the magnitude of its TD error.
+    pub fn update_priority(&mut self, index: usize, priority: f64) {
+        assert!(index < self.len(), "No transition at index {}.", index);
+        self.set_priority(index, priority);
+    }
+
+    fn set_priority(&mut self, index: usize, priority: f64) {
+        // Zero priority transitions would never be sampled again.
+        let priority = priority.max(1e-6);
+        self.max_priority = self.max_priority.max(priority);
+        if index == self.raw_priorities.len() {
+            self.raw_priorities.push(priority);
+        } else {
+            self.raw_priorities[index] = priority;
+        }
+        self.priorities.set(index, priority.powf(self.alpha));
+    }
+
+    /// The indices of batch_size transitions sampled uniformly with replacement.
+    pub fn sample_uniform(&self, batch_size: usize, rng: &mut Rng) -> Vec<usize> {
+        assert!(!self.is_empty(), "Cannot sample from an empty buffer.");
+        (0..batch_size).map(|_| rng.gen_index(self.len())).collect()
+    }
+
+    /// The indices of batch_size transitions sampled with replacement in proportion to
+    /// priority^alpha, each with its importance sampling weight (N * P(i))^-beta divided by the
+    /// largest weight in the batch.
+    pub fn sample_prioritized(
+        &self,
+        batch_size: usize,
+        beta: f64,
+        rng: &mut Rng,
+    ) -> Vec<(usize, f64)> {
+        assert!(!self.is_empty(), "Cannot sample from an empty buffer.");
+        let total = self.priorities.total();
+        let indices: Vec<usize> = (0..batch_size)
+            .map(|_| {
+                // Rounding can land past the last transition.
+                self.priorities
+                    .find(rng.gen_f64() * total)
+                    .min(self.len() - 1)
+            })
+            .collect();
+        let weights: Vec<f64> = indices
+            .iter()
+            .map(|&index| {
+                let probability = self.priorities.get(index) / total;
+                (self.len() as f64 * probability).powf(-beta)
+            })
+            .collect();
+        let max_weight = weights.iter().copied().fold(0.0, f64::max);
+        indices
+            .into_iter()
+            .zip(weights)
+            .map(|(index, weight)| (index, weight / max_weight))
+            .collect()
+    }
+
+    /// Writes the buffer to the file at path, as little endian integers and floats after a magic
+    /// number.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(REPLAY_MAGIC)?;
+        write_u64(&mut writer, self.capacity)?;
+        writer.write_all(&self.alpha.to_le_bytes())?;
+        writer.write_all(&self.max_priority.to_le_bytes())?;
+        write_u64(&mut writer, self.len())?;
+        for index in self.indices_by_age() {
+            let transition = &self.transitions[index];
+            write_u64(&mut writer, transition.observation.len())?;
+            for value in &transition.observation {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            write_u64(&mut writer, transition.action)?;
+            write_u64(&mut writer, transition.legal_mask.len())?;
+            let mask: Vec<u8> = transition
+                .legal_mask
+                .iter()
+                .map(|&legal| legal as u8)
+                .collect();
+            writer.write_all(&mask)?;
+            writer.write_all(&transition.reward.to_le_bytes())?;
+            writer.write_all(&[transition.player.index() as u8, transition.done as u8])?;
+            writer.write_all(&self.priority(index).to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a buffer written by save. Buffers grow as transitions are read, so lengths in a
+    /// corrupt file make it run out of data rather than allocate.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; REPLAY_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *REPLAY_MAGIC {
+            return Err(invalid_data("Not a ReplayBuffer file."));
+        }
+        let capacity = read_u64(&mut reader)?;
+        if capacity == 0 {
+            return Err(invalid_data("The capacity must be positive."));
+        }
+        let mut buffer = Self::new(capacity);
+        buffer.alpha = read_f64(&mut reader)?;
+        let max_priority = read_f64(&mut reader)?;
+        let len = read_u64(&mut reader)?;
+        if len > capacity {
+            return Err(invalid_data("More transitions than the capacity."));
+        }
+        for _ in 0..len {
+            let mut observation = vec![];
+            for _ in 0..read_u64(&mut reader)? {
+                observation.push(read_f64(&mut reader)?);
+            }
+            let action = read_u64(&mut reader)?;
+            let mask_len = read_u64(&mut reader)?;
+            let mut legal_mask = vec![];
+            (&mut reader)
+                .take(mask_len as u64)
+                .read_to_end(&mut legal_mask)?;
+            if legal_mask.len() != mask_len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let reward = read_f64(&mut reader)?;
+            let mut flags = [0; 2];
+            reader.read_exact(&mut flags)?;
+            let priority = read_f64(&mut reader)?;
+            let transition = Transition {
+                observation,
+                action,
+                legal_mask: legal_mask.into_iter().map(|legal| legal != 0).collect(),
+                reward,
+                player: TwoPlayer::new(flags[0] == 0),
+                done: flags[1] != 0,
+            };
+            buffer.insert(transition, priority);
+        }
+        buffer.max_priority = max_priority;
+        Ok(buffer)
+    }
+}
+
+const REPLAY_MAGIC: &[u8; 8] = b"RFREPLAY";
+
+fn write_u64<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes) as usize)
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::RandomEvaluator,
+        game_player::GamePlayer,
+        game_state::{outcome::Payoff, player::TwoPlayer, Features},
+        games::tic_tac_toe::{Piece, TicTacToe},
+        learning::replay::{ReplayBuffer, Transition},
+        rng::Rng,
+        strategy::GreedyStrategy,
+    };
+
+    fn transition(action: usize) -> Transition {
+        Transition {
+            observation: vec![action as f64, 0.5],
+            action,
+            legal_mask: vec![true, false, true],
+            reward: 0.0,
+            player: TwoPlayer::new(action.is_multiple_of(2)),
+            done: false,
+        }
+    }
+
+    #[test]
+    fn records_games() {
+        let root = TicTacToe::new(Piece::X);
+        let mut game_player = GamePlayer::new(root, RandomEvaluator::new(2), GreedyStrategy);
+        let (_, outcome, trajectory) = game_player.play_recorded();
+        assert!(trajectory.len() >= 5);
+        let first = &trajectory.transitions[0];
+        assert_eq!(first.legal_mask, vec![true; 9]);
+        assert_eq!(first.observation.len(), TicTacToe::N_FEATURES);
+        let done: Vec<&Transition> = trajectory.transitions.iter().filter(|t| t.done).collect();
+        assert_eq!(done.len(), 2);
+        for transition in done {
+            assert_eq!(transition.reward, outcome.payoff(&transition.player));
+        }
+        let mut buffer = ReplayBuffer::new(100);
+        buffer.extend(trajectory.clone());
+        assert_eq!(
+            buffer.iter().cloned().collect::<Vec<_>>(),
+            trajectory.transitions
+        );
+    }
+
+    #[test]
+    fn evicts_the_oldest() {
+        let mut buffer = ReplayBuffer::new(3);
+        for action in 0..5 {
+            buffer.push(transition(action));
+        }
+        assert_eq!(buffer.len(), 3);
+        let actions: Vec<usize> = buffer.iter().map(|t| t.action).collect();
+        assert_eq!(actions, vec![2, 3, 4]);
+        let mut rng = Rng::new(1);
+        assert!(buffer
+            .sample_uniform(20, &mut rng)
+            .iter()
+            .all(|&index| index < 3));
+    }
+
+    #[test]
+    fn samples_by_priority() {
+        let mut buffer = ReplayBuffer::new(5);
+        buffer.alpha = 1.0;
+        for action in 0..4 {
+            buffer.push(transition(action));
+        }
+        for (index, priority) in [1.0, 2.0, 3.0, 4.0].into_iter().enumerate() {
+            buffer.update_priority(index, priority);
+        }
+        let mut rng = Rng::new(3);
+        let mut counts = [0.0; 4];
+        let batch = buffer.sample_prioritized(10_000, 0.5, &mut rng);
+        for &(index, weight) in &batch {
+            counts[index] += 1.0;
+            assert!(weight > 0.0 && weight <= 1.0);
+        }
+        for (index, count) in counts.iter().enumerate() {
+            let expected = (index + 1) as f64 / 10.0;
+            assert!((count / 10_000.0 - expected).abs() < 0.02, "{:?}", counts);
+        }
+        // The least likely transition has the largest weight.
+        let (_, weight) = batch.iter().find(|(index, _)| *index == 0).unwrap();
+        assert_eq!(*weight, 1.0);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut buffer = ReplayBuffer::new(4);
+        for action in 0..6 {
+            buffer.push(transition(action));
+        }
+        buffer.update_priority(1, 3.0);
+        let path = std::env::temp_dir().join(format!("reinfors_replay_{}.bin", std::process::id()));
+        buffer.save(&path).unwrap();
+        let loaded = ReplayBuffer::load(&path).unwrap();
+        // Huge lengths in the header run out of data instead of allocating. The capacity comes
+        // after the magic number, and the length of the first observation after the capacity,
+        // alpha, the maximum priority and the number of transitions.
+        let bytes = std::fs::read(&path).unwrap();
+        for offset in [8, 40] {
+            let mut corrupt = bytes.clone();
+            corrupt[offset..offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+            std::fs::write(&path, &corrupt).unwrap();
+            let result = ReplayBuffer::load(&path);
+            assert_eq!(result.is_ok(), offset == 8);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.iter().collect::<Vec<_>>(),
+            buffer.iter().collect::<Vec<_>>()
+        );
+        let priorities = |buffer: &ReplayBuffer| {
+            buffer
+                .indices_by_age()
+                .map(|index| buffer.priority(index))
+                .collect::<Vec<f64>>()
+        };
+        for (loaded, saved) in priorities(&loaded).into_iter().zip(priorities(&buffer)) {
+            assert!((loaded - saved).abs() < 1e-9);
+        }
+        assert_eq!(loaded.capacity(), 4);
+    }
+
+    #[test]
+    fn uniform_priorities() {
+        let mut buffer = ReplayBuffer::new(4);
+        buffer.alpha = 0.0;
+        for action in 0..3 {
+            buffer.push(transition(action));
+        }
+        buffer.update_priority(1, 3.0);
+        assert_eq!(buffer.priority(0), 1.0);
+        assert_eq!(buffer.priority(1), 3.0);
+        let batch = buffer.sample_prioritized(10, 0.5, &mut Rng::new(2));
+        assert!(batch.iter().all(|&(_, weight)| weight == 1.0));
+    }
+}
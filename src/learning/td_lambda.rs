@@ -0,0 +1,298 @@
+use std::{collections::HashSet, hash::Hash, marker::PhantomData};
+
+use crate::{
+    evaluator::{Evaluator, MinimaxEvaluator},
+    game_state::{
+        outcome::{Payoff, WinDraw},
+        player::TwoPlayer,
+        ApplyResult::*,
+        Features, GameState,
+    },
+    learning::Schedule,
+    rng::Rng,
+    strategy::{GreedyStrategy, Strategy},
+};
+
+/// Estimates the value of a state for the player to move as a weighted sum of its Features.
+///
+/// As an Evaluator, an action is worth the payoff if it ends the game, and otherwise the negated
+/// value of the resulting state, which belongs to the opponent. GreedyStrategy therefore plays
+/// the action leading to the state that is worst for the opponent.
+#[derive(Debug, Clone)]
+pub struct LinearValueEvaluator {
+    pub weights: Vec<f64>,
+}
+
+impl LinearValueEvaluator {
+    pub fn new(n_features: usize) -> Self {
+        Self {
+            weights: vec![0.0; n_features],
+        }
+    }
+
+    pub fn value<G>(&self, state: &G) -> f64
+    where
+        G: Features,
+    {
+        self.value_of(&state.features())
+    }
+
+    /// The value of a state with the given features.
+    pub fn value_of(&self, features: &[f64]) -> f64 {
+        dot(&self.weights, features)
+    }
+}
+
+impl<G> Evaluator<G> for LinearValueEvaluator
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+{
+    type Evaluation = f64;
+
+    fn evaluate(&mut self, state: &G, action: &G::Action) -> Self::Evaluation {
+        match state.apply(action) {
+            Finished(_, outcome) => outcome.payoff(&state.current_player()),
+            Ongoing(next_state) => -self.value(&next_state),
+        }
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TdConfig {
+    pub learning_rate: Schedule,
+    /// The decay of the eligibility traces. 0 is one step TD, 1 is Monte Carlo.
+    pub lambda: f64,
+    pub discount: f64,
+    /// The probability of playing a uniformly random action instead of the greedy one.
+    pub epsilon: Schedule,
+    pub seed: u64,
+}
+
+impl Default for TdConfig {
+    fn default() -> Self {
+        Self {
+            learning_rate: Schedule::Constant(0.01),
+            lambda: 0.7,
+            discount: 1.0,
+            epsilon: Schedule::Constant(0.1),
+            seed: 0,
+        }
+    }
+}
+
+/// Learns a LinearValueEvaluator from self-play with TD(lambda) and accumulating eligibility
+/// traces.
+///
+/// Values are from the perspective of the player to move, so the TD error of a move is
+/// computed against the negated value of the next state, and the traces flip sign every ply:
+/// evidence about the next state is evidence about this one with the opposite sign.
+#[derive(Debug)]
+pub struct TdLambda<G> {
+    config: TdConfig,
+    evaluator: LinearValueEvaluator,
+    episodes: usize,
+    rng: Rng,
+    game: PhantomData<G>,
+}
+
+impl<G> TdLambda<G>
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Clone,
+    G::Action: Clone,
+{
+    pub fn new(config: TdConfig) -> Self {
+        Self {
+            config,
+            evaluator: LinearValueEvaluator::new(G::N_FEATURES),
+            episodes: 0,
+            rng: Rng::new(config.seed),
+            game: PhantomData,
+        }
+    }
+
+    pub fn episodes(&self) -> usize {
+        self.episodes
+    }
+
+    pub fn evaluator(&self) -> &LinearValueEvaluator {
+        &self.evaluator
+    }
+
+    pub fn into_evaluator(self) -> LinearValueEvaluator {
+        self.evaluator
+    }
+
+    /// Plays the given number of games from root against itself.
+    pub fn train(&mut self, root: &G, episodes: usize) {
+        for _ in 0..episodes {
+            self.episode(root);
+            self.episodes += 1;
+        }
+    }
+
+    /// Trains for the given number of episodes, scoring the evaluator before training and after
+    /// every `every` episodes. Returns the number of episodes trained at each score and the
+    /// score, e.g. minimax_agreement.
+    pub fn train_with_curve<F>(
+        &mut self,
+        root: &G,
+        episodes: usize,
+        every: usize,
+        mut score: F,
+    ) -> Vec<(usize, f64)>
+    where
+        F: FnMut(&mut LinearValueEvaluator) -> f64,
+    {
+        let mut curve = vec![(self.episodes, score(&mut self.evaluator))];
+        let mut trained = 0;
+        while trained < episodes {
+            let batch = every.min(episodes - trained);
+            self.train(root, batch);
+            trained += batch;
+            curve.push((self.episodes, score(&mut self.evaluator)));
+        }
+        curve
+    }
+
+    fn episode(&mut self, root: &G) {
+        let (lambda, discount) = (self.config.lambda, self.config.discount);
+        let learning_rate = self.config.learning_rate.value(self.episodes);
+        let mut traces = vec![0.0; G::N_FEATURES];
+        let mut state = root.clone();
+        loop {
+            let features = state.features();
+            let value = dot(&self.evaluator.weights, &features);
+            let mover = state.current_player();
+            let action = self.explore(&state);
+            let (target, next_state) = match state.apply(&action) {
+                Finished(_, outcome) => (outcome.payoff(&mover), None),
+                Ongoing(next_state) => (
+                    -discount * self.evaluator.value(&next_state),
+                    Some(next_state),
+                ),
+            };
+            let error = target - value;
+            for ((trace, feature), weight) in traces
+                .iter_mut()
+                .zip(&features)
+                .zip(&mut self.evaluator.weights)
+            {
+                *trace = -discount * lambda * *trace + feature;
+                *weight += learning_rate * error * *trace;
+            }
+            match next_state {
+                Some(next_state) => state = next_state,
+                None => return,
+            }
+        }
+    }
+
+    /// Epsilon-greedy with respect to the current evaluator.
+    fn explore(&mut self, state: &G) -> G::Action {
+        if self.rng.gen_f64() < self.config.epsilon.value(self.episodes) {
+            let actions: Vec<&G::Action> = state.legal_actions().collect();
+            return (*self
+                .rng
+                .choose(&actions)
+                .expect("Game isn't over but there were no legal moves available."))
+            .clone();
+        }
+        GreedyStrategy.choose(state, &mut self.evaluator)
+    }
+}
+
+/// Returns every state reachable from root in which the game is not over, including root.
+pub fn reachable_states<G>(root: &G) -> Vec<G>
+where
+    G: GameState + Hash + Eq + Clone,
+{
+    let mut seen = HashSet::from([root.clone()]);
+    let mut stack = vec![root.clone()];
+    while let Some(state) = stack.pop() {
+        for action in state.legal_actions() {
+            if let Ongoing(next_state) = state.apply(action) {
+                if seen.insert(next_state.clone()) {
+                    stack.push(next_state);
+                }
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+/// The fraction of the given states in which the action chosen by GreedyStrategy with the given
+/// evaluator is optimal according to the minimax evaluator.
+pub fn minimax_agreement<G, E>(
+    evaluator: &mut E,
+    minimax: &mut MinimaxEvaluator<G>,
+    states: &[G],
+) -> f64
+where
+    G: GameState<Player = TwoPlayer, Outcome = WinDraw<G>> + Hash + Eq,
+    G::Action: Clone,
+    E: Evaluator<G, Evaluation: PartialOrd>,
+{
+    let agreeing = states
+        .iter()
+        .filter(|&state| {
+            let best = state
+                .legal_actions()
+                .map(|action| minimax.evaluate(state, action))
+                .max()
+                .expect("The game isn't over.");
+            let chosen = GreedyStrategy.choose(state, evaluator);
+            minimax.evaluate(state, &chosen) == best
+        })
+        .count();
+    agreeing as f64 / states.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::MinimaxEvaluator,
+        game_player::GamePlayer,
+        game_state::Features,
+        games::{
+            connect4::Connect4,
+            tic_tac_toe::{Piece, TicTacToe},
+        },
+        learning::td_lambda::{minimax_agreement, reachable_states, TdConfig, TdLambda},
+        strategy::GreedyStrategy,
+    };
+
+    #[test]
+    fn learning_curve_on_tic_tac_toe() {
+        let root = TicTacToe::new(Piece::X);
+        let states = reachable_states(&root);
+        assert_eq!(states.len(), 4520);
+        let mut minimax = MinimaxEvaluator::new();
+        let mut learner = TdLambda::new(TdConfig::default());
+        let curve = learner.train_with_curve(&root, 2000, 1000, |evaluator| {
+            minimax_agreement(evaluator, &mut minimax, &states)
+        });
+        assert_eq!(curve.len(), 3);
+        let (first, last) = (curve[0].1, curve[2].1);
+        assert!(last > first && last > 0.9, "{:?}", curve);
+    }
+
+    #[test]
+    fn trains_on_connect4() {
+        let root = Connect4::new();
+        let mut learner = TdLambda::new(TdConfig::default());
+        learner.train(&root, 500);
+        let evaluator = learner.into_evaluator();
+        assert!(evaluator.weights.iter().all(|weight| weight.is_finite()));
+        // The last four features count the windows holding three and two pieces of only the
+        // player to move, then of only the opponent. Threatening to win is good, and facing a
+        // threat is bad.
+        let windows = &evaluator.weights[Connect4::N_FEATURES - 4..];
+        assert!(windows[0] > 0.1 && windows[1] < -0.1, "{:?}", windows);
+        let mut game_player = GamePlayer::new(root, evaluator, GreedyStrategy);
+        game_player.play();
+    }
+}
@@ -1,5 +1,16 @@
+pub mod arena;
+pub mod cache;
+pub mod cfr;
+pub mod environment;
 pub mod evaluator;
 pub mod game_player;
 pub mod game_state;
 pub mod games;
+pub mod learning;
+pub mod mdp;
+pub mod nn;
+pub mod observer;
+pub mod policy;
+pub mod rng;
+pub mod search;
 pub mod strategy;
@@ -0,0 +1,311 @@
+use std::fmt::Display;
+
+use crate::mdp::Mdp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Start,
+    Floor,
+    Wall,
+    Hole,
+    Goal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    Left,
+    Down,
+    Right,
+    Up,
+}
+
+pub static ALL_MOVES: [Move; 4] = [Move::Left, Move::Down, Move::Right, Move::Up];
+
+impl Move {
+    /// The two moves at right angles to this one, which are where a slip goes.
+    fn perpendicular(&self) -> [Move; 2] {
+        match self {
+            Move::Left | Move::Right => [Move::Up, Move::Down],
+            Move::Up | Move::Down => [Move::Left, Move::Right],
+        }
+    }
+
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Move::Left => (0, -1),
+            Move::Down => (1, 0),
+            Move::Right => (0, 1),
+            Move::Up => (-1, 0),
+        }
+    }
+}
+
+/// A grid of tiles that an agent walks on, starting from the Start tile. Walking into a wall or
+/// off the grid leaves the agent in place, and holes and goals end the episode.
+///
+/// Moves are slippery: with probability slip, the agent instead moves in one of the two
+/// directions at right angles to the one it chose, each equally likely. FrozenLake is the map
+/// from frozen_lake with a slip of 2/3, so that all three directions are equally likely.
+///
+/// States are the row major indices of the tiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridWorld {
+    width: usize,
+    tiles: Vec<Tile>,
+    pub slip: f64,
+    /// The reward for every move that doesn't end in a hole or goal.
+    pub step_reward: f64,
+    pub hole_reward: f64,
+    pub goal_reward: f64,
+    pub discount: f64,
+}
+
+impl GridWorld {
+    /// Parses a map with one string per row, where S is the start, . or F is floor, # is a wall,
+    /// H is a hole and G is a goal. Returns None unless the rows are the same non zero length
+    /// and there is exactly one start.
+    ///
+    /// Reaching a goal is worth 1, everything else 0, and the discount is 0.99.
+    pub fn from_map(map: &[&str], slip: f64) -> Option<Self> {
+        let width = map.first()?.len();
+        if width == 0 || map.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        let tiles = map
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|tile| match tile {
+                'S' => Some(Tile::Start),
+                '.' | 'F' => Some(Tile::Floor),
+                '#' => Some(Tile::Wall),
+                'H' => Some(Tile::Hole),
+                'G' => Some(Tile::Goal),
+                _ => None,
+            })
+            .collect::<Option<Vec<Tile>>>()?;
+        if tiles.iter().filter(|&&tile| tile == Tile::Start).count() != 1 {
+            return None;
+        }
+        Some(Self {
+            width,
+            tiles,
+            slip,
+            step_reward: 0.0,
+            hole_reward: 0.0,
+            goal_reward: 1.0,
+            discount: 0.99,
+        })
+    }
+
+    /// The 4x4 FrozenLake map.
+    pub fn frozen_lake() -> Self {
+        Self::from_map(&["SFFF", "FHFH", "FFFH", "HFFG"], 2.0 / 3.0)
+            .expect("The FrozenLake map is valid.")
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.tiles.len() / self.width
+    }
+
+    pub fn tile(&self, state: usize) -> Tile {
+        self.tiles[state]
+    }
+
+    pub fn start(&self) -> usize {
+        self.tiles
+            .iter()
+            .position(|&tile| tile == Tile::Start)
+            .expect("There is a start.")
+    }
+
+    pub fn is_terminal(&self, state: usize) -> bool {
+        matches!(self.tiles[state], Tile::Hole | Tile::Goal)
+    }
+
+    /// Where the agent ends up if it moves from state without slipping.
+    pub fn destination(&self, state: usize, direction: Move) -> usize {
+        let (row, column) = ((state / self.width) as isize, (state % self.width) as isize);
+        let (d_row, d_column) = direction.offset();
+        let (row, column) = (row + d_row, column + d_column);
+        if !(0..self.height() as isize).contains(&row)
+            || !(0..self.width as isize).contains(&column)
+        {
+            return state;
+        }
+        let next = row as usize * self.width + column as usize;
+        if self.tiles[next] == Tile::Wall {
+            state
+        } else {
+            next
+        }
+    }
+
+    fn reward(&self, next: usize) -> f64 {
+        match self.tiles[next] {
+            Tile::Hole => self.hole_reward,
+            Tile::Goal => self.goal_reward,
+            _ => self.step_reward,
+        }
+    }
+}
+
+impl Mdp for GridWorld {
+    type State = usize;
+
+    type Action = Move;
+
+    fn states(&self) -> Vec<Self::State> {
+        (0..self.tiles.len())
+            .filter(|&state| self.tiles[state] != Tile::Wall)
+            .collect()
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        if self.is_terminal(*state) {
+            vec![]
+        } else {
+            ALL_MOVES.to_vec()
+        }
+    }
+
+    fn transitions(&self, state: &Self::State, action: &Self::Action) -> Vec<(usize, f64, f64)> {
+        let [left, right] = action.perpendicular();
+        let mut transitions: Vec<(usize, f64, f64)> = vec![];
+        for (direction, probability) in [
+            (*action, 1.0 - self.slip),
+            (left, self.slip / 2.0),
+            (right, self.slip / 2.0),
+        ] {
+            if probability == 0.0 {
+                continue;
+            }
+            let next = self.destination(*state, direction);
+            // Merge directions that end in the same place, e.g. two walls.
+            match transitions.iter_mut().find(|(other, ..)| *other == next) {
+                Some((_, total, _)) => *total += probability,
+                None => transitions.push((next, probability, self.reward(next))),
+            }
+        }
+        transitions
+    }
+
+    fn discount(&self) -> f64 {
+        self.discount
+    }
+}
+
+impl Display for GridWorld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.tiles.chunks(self.width) {
+            for tile in row {
+                let tile = match tile {
+                    Tile::Start => 'S',
+                    Tile::Floor => '.',
+                    Tile::Wall => '#',
+                    Tile::Hole => 'H',
+                    Tile::Goal => 'G',
+                };
+                write!(f, "{}", tile)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mdp::{
+        grid_world::{GridWorld, Move},
+        modified_policy_iteration, policy_iteration, value_iteration, Mdp, MAX_SWEEPS,
+    };
+
+    #[test]
+    fn transitions_sum_to_one() {
+        let lake = GridWorld::frozen_lake();
+        for state in lake.states() {
+            for action in lake.actions(&state) {
+                let total: f64 = lake
+                    .transitions(&state, &action)
+                    .iter()
+                    .map(|(_, probability, _)| probability)
+                    .sum();
+                assert!((total - 1.0).abs() < 1e-12);
+            }
+        }
+        assert!(GridWorld::from_map(&["S.", "..."], 0.0).is_none());
+        assert!(GridWorld::from_map(&["..", ".G"], 0.0).is_none());
+    }
+
+    #[test]
+    fn deterministic_shortest_path() {
+        let mut grid = GridWorld::from_map(&["S.#", "..G"], 0.0).unwrap();
+        grid.discount = 0.9;
+        let solution = value_iteration(&grid, 1e-12);
+        // Three moves to the goal, the last of which is rewarded.
+        assert!((solution.value(&grid.start()) - 0.81).abs() < 1e-9);
+        assert_eq!(solution.action(&4), Some(&Move::Right));
+        assert_eq!(solution.action(&5), None);
+        assert_eq!(grid.to_string(), "S.#\n..G\n");
+    }
+
+    #[test]
+    fn undiscounted_shortest_path() {
+        let mut grid = GridWorld::from_map(&["S.#", "..G"], 0.0).unwrap();
+        grid.discount = 1.0;
+        grid.step_reward = -1.0;
+        // Starting with Left everywhere walks into the edge of the map forever.
+        for solution in [
+            value_iteration(&grid, 1e-12),
+            policy_iteration(&grid, 1e-12),
+            modified_policy_iteration(&grid, 5, 1e-12),
+        ] {
+            // Two steps, then the goal.
+            assert!((solution.value(&grid.start()) + 1.0).abs() < 1e-9);
+            assert_eq!(solution.action(&4), Some(&Move::Right));
+            assert!(solution.converged);
+        }
+    }
+
+    #[test]
+    fn undiscounted_without_an_exit_gives_up() {
+        let mut grid = GridWorld::from_map(&["S#G"], 0.0).unwrap();
+        grid.discount = 1.0;
+        grid.step_reward = -1.0;
+        for solution in [
+            value_iteration(&grid, 1e-12),
+            policy_iteration(&grid, 1e-12),
+            modified_policy_iteration(&grid, 5, 1e-12),
+        ] {
+            assert!(!solution.converged);
+            assert_eq!(solution.sweeps, MAX_SWEEPS);
+            // Every sweep takes one more step towards the goal that can't be reached.
+            assert_eq!(solution.value(&grid.start()), -(MAX_SWEEPS as f64));
+        }
+    }
+
+    #[test]
+    fn planners_agree_on_frozen_lake() {
+        let lake = GridWorld::frozen_lake();
+        let solutions = [
+            value_iteration(&lake, 1e-10),
+            policy_iteration(&lake, 1e-10),
+            modified_policy_iteration(&lake, 5, 1e-10),
+        ];
+        for solution in &solutions {
+            // The well known optimal value of the start of FrozenLake with a discount of 0.99.
+            assert!((solution.value(&lake.start()) - 0.542).abs() < 1e-3);
+            for state in lake.states() {
+                assert!((solution.value(&state) - solutions[0].value(&state)).abs() < 1e-6);
+            }
+        }
+        // The known optimal policy pushes against the edge of the lake at the start.
+        for solution in &solutions {
+            assert_eq!(solution.action(&lake.start()), Some(&Move::Left));
+        }
+    }
+}
@@ -0,0 +1,287 @@
+pub mod grid_world;
+
+use std::{collections::HashMap, hash::Hash};
+
+/// A Markov decision process with finitely many states and actions, for single agent planning.
+/// Unlike GameState, transitions are stochastic and given as an explicit distribution, so
+/// planners can compute expectations instead of sampling.
+pub trait Mdp {
+    type State: Clone + Eq + Hash;
+
+    type Action: Clone;
+
+    /// Every state of the process.
+    fn states(&self) -> Vec<Self::State>;
+
+    /// The actions available in state. Terminal states have none.
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action>;
+
+    /// The possible results of taking action in state: the next state, its probability and
+    /// the reward for the transition. The probabilities sum to 1.
+    fn transitions(
+        &self,
+        state: &Self::State,
+        action: &Self::Action,
+    ) -> Vec<(Self::State, f64, f64)>;
+
+    fn discount(&self) -> f64;
+}
+
+/// The optimal values and a greedy policy found by a planner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<S, A>
+where
+    S: Eq + Hash,
+{
+    pub values: HashMap<S, f64>,
+    /// The action to take in every state that isn't terminal.
+    pub policy: HashMap<S, A>,
+    /// The number of sweeps over the states, counting policy evaluation sweeps.
+    pub sweeps: usize,
+    /// False if the planner gave up after MAX_SWEEPS sweeps, in which case the values and the
+    /// policy are only those of the last sweep.
+    pub converged: bool,
+}
+
+impl<S, A> Solution<S, A>
+where
+    S: Eq + Hash,
+{
+    pub fn value(&self, state: &S) -> f64 {
+        self.values[state]
+    }
+
+    pub fn action(&self, state: &S) -> Option<&A> {
+        self.policy.get(state)
+    }
+}
+
+/// An Mdp with states replaced by indices, so the planners can work on vectors.
+struct Tabular<M>
+where
+    M: Mdp,
+{
+    states: Vec<M::State>,
+    actions: Vec<Vec<M::Action>>,
+    /// (next state index, probability, reward) for every action of every state.
+    transitions: Vec<Vec<Vec<(usize, f64, f64)>>>,
+    discount: f64,
+}
+
+impl<M> Tabular<M>
+where
+    M: Mdp,
+{
+    fn new(mdp: &M) -> Self {
+        let states = mdp.states();
+        let indices: HashMap<&M::State, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (state, index))
+            .collect();
+        let actions: Vec<Vec<M::Action>> = states.iter().map(|state| mdp.actions(state)).collect();
+        let transitions = states
+            .iter()
+            .zip(&actions)
+            .map(|(state, actions)| {
+                actions
+                    .iter()
+                    .map(|action| {
+                        mdp.transitions(state, action)
+                            .into_iter()
+                            .map(|(next, probability, reward)| {
+                                let next = *indices
+                                    .get(&next)
+                                    .expect("A transition led to a state missing from states.");
+                                (next, probability, reward)
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            states,
+            actions,
+            transitions,
+            discount: mdp.discount(),
+        }
+    }
+
+    /// The expected reward plus discounted value of taking action in state.
+    fn q_value(&self, values: &[f64], state: usize, action: usize) -> f64 {
+        self.transitions[state][action]
+            .iter()
+            .map(|&(next, probability, reward)| {
+                probability * (reward + self.discount * values[next])
+            })
+            .sum()
+    }
+
+    /// The best action and its value, or None in terminal states.
+    fn best(&self, values: &[f64], state: usize) -> Option<(usize, f64)> {
+        (0..self.actions[state].len())
+            .map(|action| (action, self.q_value(values, state, action)))
+            .fold(None, |best, (action, value)| match best {
+                Some((_, best_value)) if best_value >= value => best,
+                _ => Some((action, value)),
+            })
+    }
+
+    /// One sweep of policy evaluation in place. Returns the largest change of a value.
+    fn evaluation_sweep(&self, values: &mut [f64], policy: &[Option<usize>]) -> f64 {
+        let mut delta: f64 = 0.0;
+        for (state, action) in policy.iter().enumerate() {
+            if let Some(action) = *action {
+                let value = self.q_value(values, state, action);
+                delta = delta.max((value - values[state]).abs());
+                values[state] = value;
+            }
+        }
+        delta
+    }
+
+    /// Makes policy greedy with respect to values, keeping the current action unless another is
+    /// strictly better so that ties can't make the policy cycle. Returns true if it changed.
+    fn improve(&self, values: &[f64], policy: &mut [Option<usize>]) -> bool {
+        let mut changed = false;
+        for (state, current) in policy.iter_mut().enumerate() {
+            let Some((best, best_value)) = self.best(values, state) else {
+                continue;
+            };
+            let keep = current
+                .is_some_and(|action| self.q_value(values, state, action) >= best_value - 1e-12);
+            if !keep {
+                *current = Some(best);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn solution(
+        self,
+        values: Vec<f64>,
+        policy: Vec<Option<usize>>,
+        sweeps: usize,
+        converged: bool,
+    ) -> Solution<M::State, M::Action> {
+        let policy = self
+            .states
+            .iter()
+            .zip(self.actions)
+            .zip(policy)
+            .filter_map(|((state, mut actions), action)| {
+                action.map(|action| (state.clone(), actions.swap_remove(action)))
+            })
+            .collect();
+        let values = self.states.into_iter().zip(values).collect();
+        Solution {
+            values,
+            policy,
+            sweeps,
+            converged,
+        }
+    }
+}
+
+/// The most sweeps any planner makes in total. Without a discount, the values of states that
+/// can't reach a terminal state diverge, so the planners would never converge.
+pub const MAX_SWEEPS: usize = 100_000;
+
+/// Value iteration: sweeps the Bellman optimality update over the states until no value changes
+/// by more than tolerance, or for MAX_SWEEPS sweeps, then plays greedily with respect to the
+/// values.
+pub fn value_iteration<M>(mdp: &M, tolerance: f64) -> Solution<M::State, M::Action>
+where
+    M: Mdp,
+{
+    let tabular = Tabular::new(mdp);
+    let mut values = vec![0.0; tabular.states.len()];
+    let mut sweeps = 0;
+    let mut converged = false;
+    while !converged && sweeps < MAX_SWEEPS {
+        let mut delta: f64 = 0.0;
+        for state in 0..values.len() {
+            if let Some((_, value)) = tabular.best(&values, state) {
+                delta = delta.max((value - values[state]).abs());
+                values[state] = value;
+            }
+        }
+        sweeps += 1;
+        converged = delta < tolerance;
+    }
+    let mut policy = vec![None; values.len()];
+    tabular.improve(&values, &mut policy);
+    tabular.solution(values, policy, sweeps, converged)
+}
+
+/// The most sweeps policy_iteration spends evaluating one policy. Without a discount, the
+/// values of a policy that never ends an episode, like walking into a wall forever, diverge, so
+/// the evaluation has to be cut short for the policy to be improved.
+pub const MAX_EVALUATION_SWEEPS: usize = 10_000;
+
+/// Policy iteration: evaluates the current policy until no value changes by more than
+/// tolerance, or for MAX_EVALUATION_SWEEPS sweeps, makes the policy greedy with respect to those
+/// values, and stops once it is stable, or after MAX_SWEEPS sweeps in total.
+pub fn policy_iteration<M>(mdp: &M, tolerance: f64) -> Solution<M::State, M::Action>
+where
+    M: Mdp,
+{
+    iterate_policies(mdp, tolerance, MAX_EVALUATION_SWEEPS)
+}
+
+/// Modified policy iteration: like policy_iteration, but each evaluation stops after at most the
+/// given number of sweeps. One sweep behaves like value iteration, and unlimited sweeps like
+/// policy iteration. Stops once the policy is stable and the last evaluation converged, or after
+/// MAX_SWEEPS sweeps in total.
+pub fn modified_policy_iteration<M>(
+    mdp: &M,
+    evaluation_sweeps: usize,
+    tolerance: f64,
+) -> Solution<M::State, M::Action>
+where
+    M: Mdp,
+{
+    assert!(
+        evaluation_sweeps > 0,
+        "Evaluation needs at least one sweep."
+    );
+    iterate_policies(mdp, tolerance, evaluation_sweeps)
+}
+
+fn iterate_policies<M>(
+    mdp: &M,
+    tolerance: f64,
+    evaluation_sweeps: usize,
+) -> Solution<M::State, M::Action>
+where
+    M: Mdp,
+{
+    let tabular = Tabular::new(mdp);
+    let mut values = vec![0.0; tabular.states.len()];
+    // Start from the first action in every state.
+    let mut policy: Vec<Option<usize>> = tabular
+        .actions
+        .iter()
+        .map(|actions| (!actions.is_empty()).then_some(0))
+        .collect();
+    let mut sweeps = 0;
+    loop {
+        let mut converged = false;
+        for _ in 0..evaluation_sweeps.min(MAX_SWEEPS - sweeps) {
+            sweeps += 1;
+            if tabular.evaluation_sweep(&mut values, &policy) < tolerance {
+                converged = true;
+                break;
+            }
+        }
+        let changed = tabular.improve(&values, &mut policy);
+        if converged && !changed {
+            return tabular.solution(values, policy, sweeps, true);
+        }
+        if sweeps == MAX_SWEEPS {
+            return tabular.solution(values, policy, sweeps, false);
+        }
+    }
+}
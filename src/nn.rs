@@ -0,0 +1,690 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{cache::invalid_data, rng::Rng};
+
+/// The nonlinearity applied to the output of a dense layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Identity,
+    Relu,
+    Tanh,
+}
+
+impl Activation {
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Tanh => x.tanh(),
+        }
+    }
+
+    /// The derivative, in terms of the output y = apply(x).
+    fn derivative(&self, y: f64) -> f64 {
+        match self {
+            Activation::Identity => 1.0,
+            Activation::Relu => {
+                if y > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Activation::Tanh => 1.0 - y * y,
+        }
+    }
+}
+
+/// A fully connected layer. Its weights are stored row by row, one row per output, at offset in
+/// the parameters of the network, followed by one bias per output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Dense {
+    inputs: usize,
+    outputs: usize,
+    activation: Activation,
+    offset: usize,
+}
+
+impl Dense {
+    fn n_params(&self) -> usize {
+        (self.inputs + 1) * self.outputs
+    }
+
+    fn forward(&self, params: &[f64], input: &[f64]) -> Vec<f64> {
+        let (weights, biases) = self.split(params);
+        weights
+            .chunks(self.inputs)
+            .zip(biases)
+            .map(|(row, bias)| self.activation.apply(dot(row, input) + bias))
+            .collect()
+    }
+
+    /// Adds the gradient of the parameters to grads and returns the gradient of the input, given
+    /// the input and output of the forward pass and the gradient of the output.
+    fn backward(
+        &self,
+        params: &[f64],
+        input: &[f64],
+        output: &[f64],
+        output_grad: &[f64],
+        grads: &mut [f64],
+    ) -> Vec<f64> {
+        let (weights, _) = self.split(params);
+        let (weight_grads, bias_grads) = self.split_mut(grads);
+        let mut input_grad = vec![0.0; self.inputs];
+        for (j, (&y, &grad)) in output.iter().zip(output_grad).enumerate() {
+            let delta = grad * self.activation.derivative(y);
+            if delta == 0.0 {
+                continue;
+            }
+            bias_grads[j] += delta;
+            let row = j * self.inputs..(j + 1) * self.inputs;
+            for ((weight_grad, &weight), (&x, x_grad)) in weight_grads[row.clone()]
+                .iter_mut()
+                .zip(&weights[row])
+                .zip(input.iter().zip(&mut input_grad))
+            {
+                *weight_grad += delta * x;
+                *x_grad += delta * weight;
+            }
+        }
+        input_grad
+    }
+
+    fn split<'a>(&self, params: &'a [f64]) -> (&'a [f64], &'a [f64]) {
+        params[self.offset..self.offset + self.n_params()].split_at(self.inputs * self.outputs)
+    }
+
+    fn split_mut<'a>(&self, params: &'a mut [f64]) -> (&'a mut [f64], &'a mut [f64]) {
+        params[self.offset..self.offset + self.n_params()].split_at_mut(self.inputs * self.outputs)
+    }
+}
+
+/// Builds a stack of dense layers, appending the initial values of their parameters to params.
+/// Weights are drawn uniformly from the Glorot range and biases are 0.
+fn stack(
+    inputs: usize,
+    layers: &[(usize, Activation)],
+    params: &mut Vec<f64>,
+    rng: &mut Rng,
+) -> Vec<Dense> {
+    let mut inputs = inputs;
+    layers
+        .iter()
+        .map(|&(outputs, activation)| {
+            let layer = Dense {
+                inputs,
+                outputs,
+                activation,
+                offset: params.len(),
+            };
+            let limit = (6.0 / (inputs + outputs) as f64).sqrt();
+            params.extend((0..inputs * outputs).map(|_| limit * (2.0 * rng.gen_f64() - 1.0)));
+            params.extend(std::iter::repeat_n(0.0, outputs));
+            inputs = outputs;
+            layer
+        })
+        .collect()
+}
+
+/// Returns the input followed by the output of every layer.
+fn forward_stack(layers: &[Dense], params: &[f64], input: &[f64]) -> Vec<Vec<f64>> {
+    let mut activations = vec![input.to_vec()];
+    for layer in layers {
+        let output = layer.forward(params, activations.last().expect("There is an input."));
+        activations.push(output);
+    }
+    activations
+}
+
+fn backward_stack(
+    layers: &[Dense],
+    params: &[f64],
+    activations: &[Vec<f64>],
+    output_grad: &[f64],
+    grads: &mut [f64],
+) -> Vec<f64> {
+    let mut grad = output_grad.to_vec();
+    for (i, layer) in layers.iter().enumerate().rev() {
+        grad = layer.backward(params, &activations[i], &activations[i + 1], &grad, grads);
+    }
+    grad
+}
+
+/// A multilayer perceptron: a stack of dense layers with all of its parameters in one flat
+/// vector, so optimizers and gradients don't need to know about the layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mlp {
+    inputs: usize,
+    layers: Vec<Dense>,
+    pub params: Vec<f64>,
+}
+
+impl Mlp {
+    /// A network with the given number of inputs and the given size and activation for each
+    /// layer, initialized deterministically from the seed.
+    pub fn new(inputs: usize, layers: &[(usize, Activation)], seed: u64) -> Self {
+        let mut params = vec![];
+        let layers = stack(inputs, layers, &mut params, &mut Rng::new(seed));
+        Self {
+            inputs,
+            layers,
+            params,
+        }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.layers
+            .last()
+            .map_or(self.inputs, |layer| layer.outputs)
+    }
+
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.forward_trace(input)
+            .pop()
+            .expect("There is an output.")
+    }
+
+    /// Runs the network on input, adds the gradient of the parameters with respect to the loss
+    /// to grads and returns the output. loss_grad is given the output and returns the gradient of
+    /// the loss with respect to it.
+    pub fn backward<F>(&self, input: &[f64], loss_grad: F, grads: &mut [f64]) -> Vec<f64>
+    where
+        F: FnOnce(&[f64]) -> Vec<f64>,
+    {
+        let activations = self.forward_trace(input);
+        let output = activations.last().expect("There is an output.");
+        let output_grad = loss_grad(output);
+        backward_stack(
+            &self.layers,
+            &self.params,
+            &activations,
+            &output_grad,
+            grads,
+        );
+        output.clone()
+    }
+
+    fn forward_trace(&self, input: &[f64]) -> Vec<Vec<f64>> {
+        assert_eq!(input.len(), self.inputs, "Wrong number of inputs.");
+        forward_stack(&self.layers, &self.params, input)
+    }
+}
+
+/// A shared trunk of dense layers feeding a policy head, whose logits are masked to the legal
+/// actions and turned into probabilities by softmax, and a value head with a single tanh output
+/// in [-1, 1].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyValueNet {
+    inputs: usize,
+    trunk: Vec<Dense>,
+    policy: Vec<Dense>,
+    value: Vec<Dense>,
+    pub params: Vec<f64>,
+}
+
+impl PolicyValueNet {
+    /// A network with the given number of inputs, ReLU hidden layers of the given sizes in the
+    /// trunk and one output per action, initialized deterministically from the seed.
+    pub fn new(inputs: usize, hidden: &[usize], n_actions: usize, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut params = vec![];
+        let hidden_layers: Vec<(usize, Activation)> = hidden
+            .iter()
+            .map(|&size| (size, Activation::Relu))
+            .collect();
+        let trunk = stack(inputs, &hidden_layers, &mut params, &mut rng);
+        let features = hidden.last().copied().unwrap_or(inputs);
+        let policy = stack(
+            features,
+            &[(n_actions, Activation::Identity)],
+            &mut params,
+            &mut rng,
+        );
+        let value = stack(features, &[(1, Activation::Tanh)], &mut params, &mut rng);
+        Self {
+            inputs,
+            trunk,
+            policy,
+            value,
+            params,
+        }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn n_actions(&self) -> usize {
+        self.policy[0].outputs
+    }
+
+    /// The sizes of the hidden layers of the trunk.
+    pub fn hidden(&self) -> Vec<usize> {
+        self.trunk.iter().map(|layer| layer.outputs).collect()
+    }
+
+    /// Writes the shape and parameters of the network to the file at path, as little endian
+    /// integers and floats after a magic number.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(CHECKPOINT_MAGIC)?;
+        let hidden = self.hidden();
+        let header = [self.inputs, hidden.len()]
+            .into_iter()
+            .chain(hidden)
+            .chain([self.n_actions(), self.params.len()]);
+        for value in header {
+            writer.write_all(&(value as u64).to_le_bytes())?;
+        }
+        for param in &self.params {
+            writer.write_all(&param.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a network written by save. The shape in the header is checked against the size of
+    /// the file before anything is allocated, so corrupt files are errors rather than aborts.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; CHECKPOINT_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *CHECKPOINT_MAGIC {
+            return Err(invalid_data("Not a PolicyValueNet checkpoint."));
+        }
+        let mut read_u64 = || -> io::Result<u64> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+        let inputs = read_u64()?;
+        let mut hidden = vec![];
+        // Every size is read before it is stored, so a wrong count runs out of file instead of
+        // memory.
+        for _ in 0..read_u64()? {
+            hidden.push(read_u64()?);
+        }
+        let n_actions = read_u64()?;
+        let n_params = read_u64()?;
+        if param_count(inputs, &hidden, n_actions) != Some(n_params) {
+            return Err(invalid_data(
+                "The number of parameters doesn't match the shape.",
+            ));
+        }
+        let header_len = (CHECKPOINT_MAGIC.len() + 8 * (hidden.len() + 4)) as u64;
+        if n_params.checked_mul(8) != file_len.checked_sub(header_len) {
+            return Err(invalid_data(
+                "The file doesn't hold exactly the parameters of the network.",
+            ));
+        }
+        let to_usize = |value: u64| {
+            usize::try_from(value).map_err(|_| invalid_data("The network is too large."))
+        };
+        let hidden = hidden
+            .into_iter()
+            .map(to_usize)
+            .collect::<io::Result<Vec<usize>>>()?;
+        let mut net = Self::new(to_usize(inputs)?, &hidden, to_usize(n_actions)?, 0);
+        for param in &mut net.params {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            *param = f64::from_le_bytes(bytes);
+        }
+        Ok(net)
+    }
+
+    /// Returns the probability of every action, 0 where mask is false, and the value.
+    pub fn predict(&self, input: &[f64], mask: &[bool]) -> (Vec<f64>, f64) {
+        let trunk = forward_stack(&self.trunk, &self.params, input);
+        let features = trunk.last().expect("There is an output.");
+        let logits = forward_stack(&self.policy, &self.params, features);
+        let value = forward_stack(&self.value, &self.params, features);
+        (
+            masked_softmax(&logits[1], mask),
+            value.last().expect("There is an output.")[0],
+        )
+    }
+
+    /// Adds the gradient of the loss on one sample to grads and returns the loss: the cross
+    /// entropy between the predicted and target policies plus the squared error of the value.
+    pub fn backward(
+        &self,
+        input: &[f64],
+        mask: &[bool],
+        target_policy: &[f64],
+        target_value: f64,
+        grads: &mut [f64],
+    ) -> f64 {
+        assert_eq!(input.len(), self.inputs, "Wrong number of inputs.");
+        let trunk = forward_stack(&self.trunk, &self.params, input);
+        let features = trunk.last().expect("There is an output.");
+
+        let logits = forward_stack(&self.policy, &self.params, features);
+        let probabilities = masked_softmax(&logits[1], mask);
+        let (policy_loss, logit_grad) = cross_entropy(&probabilities, target_policy);
+        let mut feature_grad =
+            backward_stack(&self.policy, &self.params, &logits, &logit_grad, grads);
+
+        let value = forward_stack(&self.value, &self.params, features);
+        let (value_loss, value_grad) = squared_error(&value[1], &[target_value]);
+        let value_feature_grad =
+            backward_stack(&self.value, &self.params, &value, &value_grad, grads);
+        for (grad, value_grad) in feature_grad.iter_mut().zip(value_feature_grad) {
+            *grad += value_grad;
+        }
+
+        backward_stack(&self.trunk, &self.params, &trunk, &feature_grad, grads);
+        policy_loss + value_loss
+    }
+}
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"RFPVNET1";
+
+/// The number of parameters of a PolicyValueNet of the given shape, or None if it doesn't fit
+/// in a u64.
+fn param_count(inputs: u64, hidden: &[u64], n_actions: u64) -> Option<u64> {
+    let dense = |inputs: u64, outputs: u64| inputs.checked_mul(outputs)?.checked_add(outputs);
+    let mut count: u64 = 0;
+    let mut features = inputs;
+    for &size in hidden {
+        count = count.checked_add(dense(features, size)?)?;
+        features = size;
+    }
+    count
+        .checked_add(dense(features, n_actions)?)?
+        .checked_add(dense(features, 1)?)
+}
+
+/// The softmax of the logits where mask is true, and 0 elsewhere. If nothing is masked in,
+/// every probability is 0.
+pub fn masked_softmax(logits: &[f64], mask: &[bool]) -> Vec<f64> {
+    // Subtracting the largest logit keeps exp from overflowing.
+    let max = logits
+        .iter()
+        .zip(mask)
+        .filter(|(_, &legal)| legal)
+        .map(|(&logit, _)| logit)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = logits
+        .iter()
+        .zip(mask)
+        .map(|(&logit, &legal)| if legal { (logit - max).exp() } else { 0.0 })
+        .collect();
+    let total: f64 = exps.iter().sum();
+    if total == 0.0 {
+        return exps;
+    }
+    exps.into_iter().map(|exp| exp / total).collect()
+}
+
+pub fn softmax(logits: &[f64]) -> Vec<f64> {
+    masked_softmax(logits, &vec![true; logits.len()])
+}
+
+/// The cross entropy of the target distribution relative to the probabilities, which came from
+/// a softmax, and its gradient with respect to the logits of the softmax.
+pub fn cross_entropy(probabilities: &[f64], target: &[f64]) -> (f64, Vec<f64>) {
+    let loss = probabilities
+        .iter()
+        .zip(target)
+        .filter(|(_, &t)| t > 0.0)
+        .map(|(&p, &t)| -t * p.max(f64::MIN_POSITIVE).ln())
+        .sum();
+    let grad = probabilities
+        .iter()
+        .zip(target)
+        .map(|(&p, &t)| p - t)
+        .collect();
+    (loss, grad)
+}
+
+/// The sum of squared errors and its gradient with respect to the output.
+pub fn squared_error(output: &[f64], target: &[f64]) -> (f64, Vec<f64>) {
+    let loss = output
+        .iter()
+        .zip(target)
+        .map(|(y, t)| (y - t) * (y - t))
+        .sum();
+    let grad = output
+        .iter()
+        .zip(target)
+        .map(|(y, t)| 2.0 * (y - t))
+        .collect();
+    (loss, grad)
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Updates parameters given the gradient of the loss with respect to them.
+pub trait Optimizer {
+    fn step(&mut self, params: &mut [f64], grads: &[f64]);
+}
+
+/// Stochastic gradient descent with momentum. A momentum of 0 is plain gradient descent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sgd {
+    pub learning_rate: f64,
+    pub momentum: f64,
+    velocity: Vec<f64>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64, momentum: f64) -> Self {
+        Self {
+            learning_rate,
+            momentum,
+            velocity: vec![],
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, params: &mut [f64], grads: &[f64]) {
+        self.velocity.resize(params.len(), 0.0);
+        for ((param, &grad), velocity) in params.iter_mut().zip(grads).zip(&mut self.velocity) {
+            *velocity = self.momentum * *velocity - self.learning_rate * grad;
+            *param += *velocity;
+        }
+    }
+}
+
+/// Adam, with bias corrected estimates of the mean and uncentered variance of the gradients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adam {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    mean: Vec<f64>,
+    variance: Vec<f64>,
+    steps: i32,
+}
+
+impl Adam {
+    /// Adam with the usual defaults: beta1 0.9, beta2 0.999 and epsilon 1e-8.
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            mean: vec![],
+            variance: vec![],
+            steps: 0,
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, params: &mut [f64], grads: &[f64]) {
+        self.mean.resize(params.len(), 0.0);
+        self.variance.resize(params.len(), 0.0);
+        self.steps += 1;
+        let mean_correction = 1.0 - self.beta1.powi(self.steps);
+        let variance_correction = 1.0 - self.beta2.powi(self.steps);
+        for (((param, &grad), mean), variance) in params
+            .iter_mut()
+            .zip(grads)
+            .zip(&mut self.mean)
+            .zip(&mut self.variance)
+        {
+            *mean = self.beta1 * *mean + (1.0 - self.beta1) * grad;
+            *variance = self.beta2 * *variance + (1.0 - self.beta2) * grad * grad;
+            let mean = *mean / mean_correction;
+            let variance = *variance / variance_correction;
+            *param -= self.learning_rate * mean / (variance.sqrt() + self.epsilon);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::{
+        squared_error, Activation, Adam, Mlp, Optimizer, PolicyValueNet, Sgd, CHECKPOINT_MAGIC,
+    };
+
+    const EPSILON: f64 = 1e-6;
+
+    /// Compares the gradients from backpropagation with central differences of the loss.
+    fn check_gradients<F>(params: &mut [f64], analytic: &[f64], mut loss: F)
+    where
+        F: FnMut(&[f64]) -> f64,
+    {
+        for i in 0..params.len() {
+            let original = params[i];
+            params[i] = original + EPSILON;
+            let plus = loss(params);
+            params[i] = original - EPSILON;
+            let minus = loss(params);
+            params[i] = original;
+            let numeric = (plus - minus) / (2.0 * EPSILON);
+            assert!(
+                (numeric - analytic[i]).abs() < 1e-5 * (1.0 + numeric.abs()),
+                "parameter {}: numeric {} analytic {}",
+                i,
+                numeric,
+                analytic[i]
+            );
+        }
+    }
+
+    #[test]
+    fn mlp_gradient_check() {
+        let layers = [
+            (5, Activation::Tanh),
+            (4, Activation::Relu),
+            (2, Activation::Identity),
+        ];
+        let mut mlp = Mlp::new(3, &layers, 7);
+        let (input, target) = ([0.5, -1.0, 0.25], [0.3, -0.7]);
+        let mut grads = vec![0.0; mlp.params.len()];
+        mlp.backward(
+            &input,
+            |output| squared_error(output, &target).1,
+            &mut grads,
+        );
+        let mut params = mlp.params.clone();
+        check_gradients(&mut params, &grads, |params| {
+            mlp.params.copy_from_slice(params);
+            squared_error(&mlp.forward(&input), &target).0
+        });
+    }
+
+    #[test]
+    fn policy_value_gradient_check() {
+        let mut net = PolicyValueNet::new(4, &[6, 5], 3, 2);
+        let input = [1.0, 0.0, -0.5, 0.75];
+        let mask = [true, false, true];
+        let target_policy = [0.25, 0.0, 0.75];
+        let mut grads = vec![0.0; net.params.len()];
+        net.backward(&input, &mask, &target_policy, -0.5, &mut grads);
+        let mut params = net.params.clone();
+        check_gradients(&mut params, &grads, |params| {
+            net.params.copy_from_slice(params);
+            net.backward(
+                &input,
+                &mask,
+                &target_policy,
+                -0.5,
+                &mut vec![0.0; params.len()],
+            )
+        });
+        let (policy, value) = net.predict(&input, &mask);
+        assert_eq!(policy[1], 0.0);
+        assert!((policy.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((-1.0..=1.0).contains(&value));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let net = PolicyValueNet::new(5, &[7, 3], 4, 9);
+        let path = std::env::temp_dir().join(format!("reinfors_nn_{}.net", std::process::id()));
+        net.save(&path).unwrap();
+        let loaded = PolicyValueNet::load(&path).unwrap();
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(PolicyValueNet::load(&path).is_err());
+        // A header promising a huge network, or a truncated file, is an error, not an abort.
+        let mut huge = CHECKPOINT_MAGIC.to_vec();
+        for value in [1u64 << 40, 1, 1 << 40, 4, u64::MAX] {
+            huge.extend(value.to_le_bytes());
+        }
+        std::fs::write(&path, &huge).unwrap();
+        assert!(PolicyValueNet::load(&path).is_err());
+        net.save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        assert!(PolicyValueNet::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, net);
+    }
+
+    #[test]
+    fn initialization_is_deterministic() {
+        let layers = [(8, Activation::Relu), (1, Activation::Tanh)];
+        assert_eq!(Mlp::new(2, &layers, 3), Mlp::new(2, &layers, 3));
+        assert_ne!(Mlp::new(2, &layers, 3), Mlp::new(2, &layers, 4));
+    }
+
+    fn xor_loss<O: Optimizer>(mut optimizer: O, steps: usize) -> f64 {
+        let data = [
+            ([0.0, 0.0], 0.0),
+            ([0.0, 1.0], 1.0),
+            ([1.0, 0.0], 1.0),
+            ([1.0, 1.0], 0.0),
+        ];
+        let layers = [(8, Activation::Tanh), (1, Activation::Identity)];
+        let mut mlp = Mlp::new(2, &layers, 1);
+        for _ in 0..steps {
+            let mut grads = vec![0.0; mlp.params.len()];
+            for (input, target) in &data {
+                mlp.backward(
+                    input,
+                    |output| squared_error(output, &[*target]).1,
+                    &mut grads,
+                );
+            }
+            optimizer.step(&mut mlp.params, &grads);
+        }
+        data.iter()
+            .map(|(input, target)| squared_error(&mlp.forward(input), &[*target]).0)
+            .sum()
+    }
+
+    #[test]
+    fn optimizers_fit_xor() {
+        assert!(xor_loss(Sgd::new(0.05, 0.9), 2000) < 0.01);
+        assert!(xor_loss(Adam::new(0.01), 2000) < 0.01);
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt::{Debug, Display},
+    io::{self, Write},
+};
+
+use crate::{
+    game_state::{outcome::Payoff, player::TwoPlayer, EnumerableActions, Features, GameState},
+    learning::replay::Trajectory,
+    search::SearchStats,
+};
+
+/// Callbacks that GamePlayer and Match invoke while they play, for rendering, recording and
+/// collecting statistics without touching the game loop. Every callback does nothing by default.
+///
+/// V is the type of the evaluations passed to before_move. Match has no evaluator and uses ().
+pub trait Observer<G, V = ()>
+where
+    G: GameState,
+{
+    fn game_start(&mut self, _state: &G) {}
+
+    /// Whether before_move needs the evaluation of every legal action. They are only computed
+    /// if some observer wants them, since that costs an evaluation per action and stochastic
+    /// evaluators would draw different random numbers, changing the game.
+    fn wants_evaluations(&self) -> bool {
+        false
+    }
+
+    /// Called before the current player chooses an action. evaluations holds every legal action
+    /// with its evaluation, in the order of legal_actions, if some observer wants evaluations,
+    /// and is empty otherwise.
+    fn before_move(&mut self, _state: &G, _evaluations: &[(G::Action, V)]) {}
+
+    /// Called after the evaluator or agent chose an action in state, with the SearchStats of the
+    /// work it did for that move, including any evaluations for before_move and the nodes the
+    /// strategy counted against its Budget. GamePlayer doesn't report moves made by a person.
+    fn after_search(&mut self, _state: &G, _stats: &SearchStats) {}
+
+    /// Called after action was played in state. next is the resulting state, which is the final
+    /// state if the game ended.
+    fn after_move(&mut self, _state: &G, _action: &G::Action, _next: &G) {}
+
+    fn game_end(&mut self, _state: &G, _outcome: &G::Outcome) {}
+}
+
+/// Prints the state when the game starts and after every move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleObserver;
+
+impl<G, V> Observer<G, V> for ConsoleObserver
+where
+    G: GameState + Display,
+{
+    fn game_start(&mut self, state: &G) {
+        print!("{}", state);
+    }
+
+    fn after_move(&mut self, _state: &G, _action: &G::Action, next: &G) {
+        print!("{}", next);
+    }
+}
+
+/// Writes one line per game: the Debug form of every action, separated by spaces, then the
+/// Debug form of the outcome after a colon.
+///
+/// Callbacks can't return errors, so the first error is kept and writing stops. into_inner
+/// returns it.
+#[derive(Debug)]
+pub struct RecordWriter<W> {
+    writer: W,
+    moves: Vec<String>,
+    error: Option<io::Error>,
+}
+
+impl<W> RecordWriter<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            moves: vec![],
+            error: None,
+        }
+    }
+
+    /// Returns the writer, or the first error that occurred while writing.
+    pub fn into_inner(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.writer),
+        }
+    }
+}
+
+impl<G, V, W> Observer<G, V> for RecordWriter<W>
+where
+    G: GameState<Action: Debug, Outcome: Debug>,
+    W: Write,
+{
+    fn game_start(&mut self, _state: &G) {
+        self.moves.clear();
+    }
+
+    fn after_move(&mut self, _state: &G, action: &G::Action, _next: &G) {
+        self.moves.push(format!("{:?}", action));
+    }
+
+    fn game_end(&mut self, _state: &G, outcome: &G::Outcome) {
+        if self.error.is_none() {
+            let result = writeln!(self.writer, "{}: {:?}", self.moves.join(" "), outcome);
+            self.error = result.err();
+        }
+    }
+}
+
+/// Collects the SearchStats of every move, and optionally prints them as they arrive.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLog {
+    /// The statistics of every searched move, in the order they were played.
+    pub moves: Vec<SearchStats>,
+    pub total: SearchStats,
+    print: bool,
+}
+
+impl SearchLog {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A log that also prints the statistics of every move.
+    pub fn printing() -> Self {
+        Self {
+            print: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl<G, V> Observer<G, V> for SearchLog
+where
+    G: GameState,
+{
+    fn after_search(&mut self, _state: &G, stats: &SearchStats) {
+        if self.print {
+            println!("{}", stats);
+        }
+        self.moves.push(*stats);
+        self.total.merge(stats);
+    }
+}
+
+/// Counts games, moves, the legal actions available at every move and the results of two player
+/// games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    pub games: usize,
+    pub moves: usize,
+    /// The sum over every move of the number of legal actions.
+    pub legal_actions: usize,
+    /// The wins of Player 0 and Player 1.
+    pub wins: [usize; 2],
+    pub draws: usize,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.moves as f64 / self.games as f64
+    }
+
+    /// The mean number of legal actions per move.
+    pub fn mean_branching(&self) -> f64 {
+        self.legal_actions as f64 / self.moves as f64
+    }
+}
+
+impl<G, V> Observer<G, V> for Statistics
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+{
+    fn before_move(&mut self, state: &G, _evaluations: &[(G::Action, V)]) {
+        self.moves += 1;
+        self.legal_actions += state.legal_actions().count();
+    }
+
+    fn game_end(&mut self, _state: &G, outcome: &G::Outcome) {
+        self.games += 1;
+        let payoff = outcome.payoff(&TwoPlayer::new(true));
+        if payoff > 0.0 {
+            self.wins[0] += 1;
+        } else if payoff < 0.0 {
+            self.wins[1] += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+}
+
+/// Records the game as a Trajectory.
+impl<G, V> Observer<G, V> for Trajectory
+where
+    G: Features<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + EnumerableActions,
+{
+    fn after_move(&mut self, state: &G, action: &G::Action, _next: &G) {
+        self.record(state, action);
+    }
+
+    fn game_end(&mut self, _state: &G, outcome: &G::Outcome) {
+        self.finish(outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_player::GamePlayer,
+        game_state::GameState,
+        games::tic_tac_toe::{Action, Piece, TicTacToe},
+        observer::{Observer, RecordWriter, SearchLog, Statistics},
+        strategy::GreedyStrategy,
+    };
+
+    /// Checks that every legal action is evaluated before every move.
+    struct EvaluationChecker {
+        moves: usize,
+    }
+
+    impl Observer<TicTacToe, i8> for EvaluationChecker {
+        fn wants_evaluations(&self) -> bool {
+            true
+        }
+
+        fn before_move(&mut self, state: &TicTacToe, evaluations: &[(Action, i8)]) {
+            assert_eq!(evaluations.len(), state.legal_actions().count());
+            // Perfect play from the start is a draw, and there is always a move that keeps it.
+            assert!(evaluations.iter().any(|(_, evaluation)| *evaluation == 0));
+            self.moves += 1;
+        }
+    }
+
+    #[test]
+    fn records_and_statistics() {
+        let root = TicTacToe::new(Piece::X);
+        let mut writer = RecordWriter::new(vec![]);
+        let mut statistics = Statistics::new();
+        let mut game_player = GamePlayer::new(root, RandomEvaluator::new(3), GreedyStrategy);
+        let mut finals = vec![];
+        for _ in 0..20 {
+            game_player.state = root;
+            finals.push(
+                game_player
+                    .play_observed(&mut [&mut writer, &mut statistics])
+                    .0,
+            );
+        }
+        let record = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(record.lines().count(), 20);
+        let moves: usize = record
+            .lines()
+            .map(|line| line.split(':').next().unwrap().split(' ').count())
+            .sum();
+        assert_eq!(statistics.games, 20);
+        assert_eq!(statistics.moves, moves);
+        assert_eq!(
+            statistics.wins[0] + statistics.wins[1] + statistics.draws,
+            20
+        );
+        assert!((5.0..=9.0).contains(&statistics.mean_length()));
+        // Observers that don't want evaluations don't change the games.
+        let mut game_player = GamePlayer::new(root, RandomEvaluator::new(3), GreedyStrategy);
+        for expected in finals {
+            game_player.state = root;
+            assert_eq!(game_player.play().0, expected);
+        }
+    }
+
+    #[test]
+    fn evaluations_before_moves() {
+        let root = TicTacToe::new(Piece::X);
+        let mut checker = EvaluationChecker { moves: 0 };
+        let mut game_player = GamePlayer::new(root, MinimaxEvaluator::new(), GreedyStrategy);
+        game_player.play_observed(&mut [&mut checker]);
+        assert_eq!(checker.moves, 9);
+    }
+
+    #[test]
+    fn search_statistics_per_move() {
+        let root = TicTacToe::new(Piece::X);
+        let mut log = SearchLog::new();
+        let mut game_player = GamePlayer::new(root, MinimaxEvaluator::new(), GreedyStrategy);
+        game_player.play_observed(&mut [&mut log]);
+        assert_eq!(log.moves.len(), 9);
+        let first = log.moves[0];
+        // The first move searches the whole tree, filling the cache.
+        assert!(first.nodes > 1000, "{}", first);
+        assert!(first.cache_hits > 0 && first.cache_misses > 0);
+        assert_eq!(first.max_depth, 9);
+        assert!(log.moves[1..].iter().all(|stats| stats.nodes < first.nodes));
+        // Later moves only look up the states after their actions, which are all cached.
+        assert_eq!(log.moves[4].cache_misses, 0);
+        assert_eq!(
+            log.total.nodes,
+            log.moves.iter().map(|stats| stats.nodes).sum()
+        );
+        assert_eq!(log.total.cache_size, log.moves[8].cache_size);
+        assert!(log.total.cache_size > 0);
+    }
+}
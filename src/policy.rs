@@ -0,0 +1,203 @@
+use crate::{evaluator::Evaluator, game_state::GameState, rng::Rng, strategy::Strategy};
+
+/// The trait for mixed strategies. Where a Strategy picks one action, a Policy returns a
+/// probability distribution over the legal actions, which is what equilibrium computations and
+/// exploitability measurements work with. PolicyStrategy samples from any Policy to play it.
+///
+/// Like strategies, policies assume that the game is not over.
+pub trait Policy<G>
+where
+    G: GameState,
+{
+    /// The probability of each legal action, in the order of legal_actions. The probabilities
+    /// are not negative and sum to 1.
+    fn distribution(&mut self, state: &G) -> Vec<f64>;
+}
+
+/// Returns an action sampled from the policy's distribution at state.
+pub fn sample<G, P>(policy: &mut P, state: &G, rng: &mut Rng) -> G::Action
+where
+    G: GameState,
+    G::Action: Clone,
+    P: Policy<G>,
+{
+    let distribution = policy.distribution(state);
+    assert!(
+        !distribution.is_empty(),
+        "Game isn't over but there were no legal moves available."
+    );
+    let index = rng.gen_weighted_index(&distribution);
+    state
+        .legal_actions()
+        .nth(index)
+        .expect("The distribution has one probability per legal action.")
+        .clone()
+}
+
+/// Plays every legal action with the same probability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformPolicy;
+
+impl<G> Policy<G> for UniformPolicy
+where
+    G: GameState,
+{
+    fn distribution(&mut self, state: &G) -> Vec<f64> {
+        let n = state.legal_actions().count();
+        vec![1.0 / n as f64; n]
+    }
+}
+
+/// Plays each legal action with probability proportional to exp(evaluation / temperature).
+/// Evaluations that are NaN get probability 0. A temperature of 0 plays the actions with the
+/// highest evaluation uniformly.
+///
+/// This is the Policy behind SoftmaxStrategy, for a fixed temperature.
+#[derive(Debug, Clone)]
+pub struct SoftmaxPolicy<E> {
+    pub evaluator: E,
+    pub temperature: f64,
+}
+
+impl<E> SoftmaxPolicy<E> {
+    pub fn new(evaluator: E, temperature: f64) -> Self {
+        Self {
+            evaluator,
+            temperature,
+        }
+    }
+}
+
+impl<G, E> Policy<G> for SoftmaxPolicy<E>
+where
+    G: GameState,
+    E: Evaluator<G, Evaluation: Into<f64>>,
+{
+    fn distribution(&mut self, state: &G) -> Vec<f64> {
+        let values: Vec<f64> = state
+            .legal_actions()
+            .map(|action| self.evaluator.evaluate(state, action).into())
+            .collect();
+        softmax(&values, self.temperature)
+    }
+}
+
+/// The probabilities SoftmaxPolicy gives actions with the given evaluations at the given
+/// temperature.
+pub fn softmax(values: &[f64], temperature: f64) -> Vec<f64> {
+    let values: Vec<f64> = values
+        .iter()
+        .map(|&value| {
+            if value.is_nan() {
+                f64::NEG_INFINITY
+            } else {
+                value
+            }
+        })
+        .collect();
+    let best = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = if temperature <= 0.0 || best == f64::NEG_INFINITY {
+        values
+            .iter()
+            .map(|&value| if value == best { 1.0 } else { 0.0 })
+            .collect()
+    } else {
+        // Subtracting the best evaluation keeps exp from overflowing.
+        values
+            .iter()
+            .map(|value| ((value - best) / temperature).exp())
+            .collect()
+    };
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+/// Turns a Policy into a Strategy by sampling an action from its distribution. The evaluator
+/// passed to choose is ignored.
+#[derive(Debug, Clone)]
+pub struct PolicyStrategy<P> {
+    pub policy: P,
+    rng: Rng,
+}
+
+impl<P> PolicyStrategy<P> {
+    pub fn new(policy: P, seed: u64) -> Self {
+        Self {
+            policy,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G, E, P> Strategy<G, E> for PolicyStrategy<P>
+where
+    G: GameState,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    P: Policy<G>,
+{
+    fn choose(&mut self, state: &G, _evaluator: &mut E) -> G::Action {
+        sample(&mut self.policy, state, &mut self.rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cfr::{CfrSolver, CfrVariant},
+        evaluator::{MinimaxEvaluator, RandomEvaluator},
+        game_state::GameState,
+        games::{
+            kuhn_poker::{Action, Card, KuhnPoker},
+            tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        },
+        policy::{Policy, PolicyStrategy, SoftmaxPolicy, UniformPolicy},
+        strategy::Strategy,
+    };
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    #[test]
+    fn uniform_and_softmax() {
+        assert_eq!(UniformPolicy.distribution(&position()), vec![0.2; 5]);
+        let mut greedy = SoftmaxPolicy::new(MinimaxEvaluator::new(), 0.0);
+        assert_eq!(greedy.distribution(&position()), [1.0, 0.0, 0.0, 0.0, 0.0]);
+        let mut hot = SoftmaxPolicy::new(MinimaxEvaluator::new(), 1.0);
+        let distribution = hot.distribution(&position());
+        // Winning is worth 1, blocking O draws and the rest lose.
+        let weights = [1f64, 0.0, -1.0, -1.0, -1.0].map(f64::exp);
+        let total: f64 = weights.iter().sum();
+        for (p, weight) in distribution.iter().zip(weights) {
+            assert!((p - weight / total).abs() < 1e-12, "{:?}", distribution);
+        }
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sampling_follows_tabular_policy() {
+        let mut solver = CfrSolver::new(KuhnPoker::new(), CfrVariant::Plus, 0);
+        solver.run(300);
+        let policy = solver.average_policy();
+        // Player 0 holding the Jack bets as a bluff some of the time.
+        let state = KuhnPoker::deal(Card::Jack, Card::King);
+        let distribution = policy.clone().distribution(&state);
+        let bet = state
+            .legal_actions()
+            .position(|action| *action == Action::Bet)
+            .unwrap();
+        let mut strategy = PolicyStrategy::new(policy, 4);
+        let mut random = RandomEvaluator::new(0);
+        let bets = (0..10_000)
+            .filter(|_| strategy.choose(&state, &mut random) == Action::Bet)
+            .count();
+        assert!(distribution[bet] > 0.0 && distribution[bet] < 0.34);
+        assert!((bets as f64 / 10_000.0 - distribution[bet]).abs() < 0.02);
+    }
+}
@@ -0,0 +1,231 @@
+/// A small, seedable pseudo random number generator, xoshiro256**, seeded through SplitMix64. It
+/// is not suitable for cryptography, but it is fast, has no dependencies and is completely
+/// determined by its seed, which is all that the stochastic algorithms in this crate need.
+///
+/// Every stochastic component of the crate is constructed from a u64 seed, and functions that
+/// only draw a few numbers borrow an Rng. To reproduce a whole run from one recorded seed, create
+/// one Rng from it and pass rng.next_u64() as the seed of each component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    seed: u64,
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // SplitMix64 spreads any seed, including 0, over the whole state.
+        let mut splitmix = seed;
+        let mut next = || {
+            splitmix = splitmix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = splitmix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Self {
+            seed,
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// The seed this generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// A new generator seeded from this one, for code that draws from an Rng of its own.
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a uniformly distributed float in [0, 1).
+    pub fn gen_f64(&mut self) -> f64 {
+        // The top 53 bits are exactly representable by an f64.
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Returns a uniformly distributed float in [low, high).
+    pub fn gen_f64_range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.gen_f64()
+    }
+
+    /// Returns true with the given probability.
+    pub fn gen_bool(&mut self, probability: f64) -> bool {
+        self.gen_f64() < probability
+    }
+
+    /// Returns a uniformly distributed index in 0..n. Panics if n is 0.
+    pub fn gen_index(&mut self, n: usize) -> usize {
+        assert!(n > 0, "Cannot sample an index from an empty range.");
+        // Multiply-shift maps the 64 random bits onto 0..n with negligible bias for small n.
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Returns a uniformly distributed integer in low..high. Panics if the range is empty.
+    pub fn gen_range(&mut self, low: i64, high: i64) -> i64 {
+        assert!(low < high, "Cannot sample from an empty range.");
+        let width = high.abs_diff(low);
+        let offset = ((self.next_u64() as u128 * width as u128) >> 64) as u64;
+        low.wrapping_add(offset as i64)
+    }
+
+    /// Returns a uniformly chosen element of the slice, or None if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.gen_index(items.len())])
+        }
+    }
+
+    /// Returns an index with probability proportional to its weight. The weights must not be
+    /// negative, and panics unless some weight is positive.
+    pub fn gen_weighted_index(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "Cannot sample without a positive weight.");
+        let mut target = self.gen_f64() * total;
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return i;
+            }
+            target -= weight;
+        }
+        // Rounding can leave a sliver of weight past the last index.
+        weights
+            .iter()
+            .rposition(|&weight| weight > 0.0)
+            .expect("There is a positive weight.")
+    }
+
+    /// Puts the slice in a uniformly random order, with the Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.gen_index(i + 1));
+        }
+    }
+
+    /// Returns a sample from the standard normal distribution, by the Box-Muller transform.
+    pub fn gen_normal(&mut self) -> f64 {
+        // 1 - u is in (0, 1], so its logarithm is finite.
+        let u = 1.0 - self.gen_f64();
+        let v = self.gen_f64();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+
+    /// Returns a sample from the Gamma distribution with the given shape and a scale of 1, by
+    /// the method of Marsaglia and Tsang. Panics unless shape is positive.
+    pub fn gen_gamma(&mut self, shape: f64) -> f64 {
+        assert!(shape > 0.0, "The shape must be positive.");
+        if shape < 1.0 {
+            // Gamma(shape) is Gamma(shape + 1) * U^(1 / shape).
+            let u = 1.0 - self.gen_f64();
+            return self.gen_gamma(shape + 1.0) * u.powf(1.0 / shape);
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let x = self.gen_normal();
+            let v = (1.0 + c * x).powi(3);
+            if v <= 0.0 {
+                continue;
+            }
+            let u = 1.0 - self.gen_f64();
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+                return d * v;
+            }
+        }
+    }
+
+    /// Returns a sample from the Dirichlet distribution with the given concentrations, which must
+    /// be positive.
+    pub fn gen_dirichlet(&mut self, alpha: &[f64]) -> Vec<f64> {
+        let gammas: Vec<f64> = alpha.iter().map(|&a| self.gen_gamma(a)).collect();
+        let total: f64 = gammas.iter().sum();
+        gammas.into_iter().map(|gamma| gamma / total).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+
+    fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+        (mean, variance)
+    }
+
+    #[test]
+    fn reproducible_from_seed() {
+        let mut rng = Rng::new(42);
+        let first: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        let mut again = Rng::new(42);
+        assert_eq!((0..5).map(|_| again.next_u64()).collect::<Vec<_>>(), first);
+        assert_ne!(Rng::new(43).next_u64(), first[0]);
+        let (mut a, mut b) = (Rng::new(1), Rng::new(1));
+        assert_eq!(a.fork(), b.fork());
+        assert_eq!(a.seed(), 1);
+    }
+
+    #[test]
+    fn uniform_ranges() {
+        let mut rng = Rng::new(3);
+        for _ in 0..1000 {
+            assert!((-3..4).contains(&rng.gen_range(-3, 4)));
+            assert!((2.0..5.0).contains(&rng.gen_f64_range(2.0, 5.0)));
+        }
+        assert_eq!(rng.gen_range(i64::MIN, i64::MIN + 1), i64::MIN);
+        let mut items: Vec<usize> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn weighted_index() {
+        let mut rng = Rng::new(5);
+        let mut counts = [0; 4];
+        for _ in 0..10_000 {
+            counts[rng.gen_weighted_index(&[1.0, 0.0, 3.0, 0.0])] += 1;
+        }
+        assert_eq!((counts[1], counts[3]), (0, 0));
+        assert!((counts[2] as f64 / 10_000.0 - 0.75).abs() < 0.02);
+    }
+
+    #[test]
+    fn gamma_and_dirichlet_moments() {
+        let mut rng = Rng::new(7);
+        for shape in [0.3, 1.0, 4.5] {
+            let samples: Vec<f64> = (0..20_000).map(|_| rng.gen_gamma(shape)).collect();
+            let (mean, variance) = mean_and_variance(&samples);
+            // Gamma(shape, 1) has mean and variance shape.
+            assert!((mean - shape).abs() < 0.05 * shape.max(1.0), "{}", mean);
+            assert!(
+                (variance - shape).abs() < 0.1 * shape.max(1.0),
+                "{}",
+                variance
+            );
+        }
+        let alpha = [0.3, 0.3, 0.6];
+        let samples: Vec<Vec<f64>> = (0..10_000).map(|_| rng.gen_dirichlet(&alpha)).collect();
+        assert!(samples
+            .iter()
+            .all(|sample| (sample.iter().sum::<f64>() - 1.0).abs() < 1e-9));
+        let last: Vec<f64> = samples.iter().map(|sample| sample[2]).collect();
+        assert!((mean_and_variance(&last).0 - 0.5).abs() < 0.02);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, GameState},
+    search::{Budget, Limiter, SearchStats},
+    strategy::Strategy,
+};
+
+/// The result of an AlphaBetaStrategy search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult<A> {
+    pub action: A,
+    /// The value of action for the player to move, according to the deepest completed search.
+    pub value: f64,
+    /// The depth of the deepest completed search, or 0 if the budget ran out during the first.
+    pub depth: usize,
+    /// True if the deepest completed search reached the end of the game in every line it
+    /// needed, so value is exact.
+    pub solved: bool,
+}
+
+/// Iterative deepening negamax search with alpha-beta pruning, for anytime play under a Budget.
+///
+/// Searches to depth 1, 2, ... until the game is solved or the budget runs out, and plays the
+/// best action of the deepest search that completed, which is searched first at the next depth.
+/// Terminal states are worth their payoff. At the depth limit the evaluator's evaluation of the
+/// last action is used as a heuristic, so it should be on the same scale as the payoffs, e.g. in
+/// [-1, 1] for WinDraw.
+///
+/// As a Strategy it reports the nodes it visited and the depth of its deepest completed search.
+#[derive(Debug, Clone, Default)]
+pub struct AlphaBetaStrategy {
+    pub budget: Budget,
+    stats: SearchStats,
+}
+
+impl AlphaBetaStrategy {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            stats: SearchStats::new(),
+        }
+    }
+
+    pub fn search<G, E>(&mut self, state: &G, evaluator: &mut E) -> SearchResult<G::Action>
+    where
+        G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+        G::Action: Clone,
+        E: Evaluator<G, Evaluation: Into<f64>>,
+    {
+        let mut limiter = self.budget.start();
+        let mut actions: Vec<G::Action> = state.legal_actions().cloned().collect();
+        assert!(
+            !actions.is_empty(),
+            "Game isn't over but there were no legal moves available."
+        );
+        let mut result = SearchResult {
+            action: actions[0].clone(),
+            value: f64::NEG_INFINITY,
+            depth: 0,
+            solved: false,
+        };
+        for depth in 1.. {
+            if limiter.depth().is_some_and(|max| depth > max) {
+                break;
+            }
+            let mut search = Search {
+                limiter: &mut limiter,
+                evaluator: &mut *evaluator,
+                heuristic: false,
+            };
+            let mut best: Option<(usize, f64)> = None;
+            let mut completed = true;
+            for (i, action) in actions.iter().enumerate() {
+                let alpha = best.map_or(f64::NEG_INFINITY, |(_, value)| value);
+                match search.action_value(state, action, depth, alpha, f64::INFINITY) {
+                    Some(value) => {
+                        if best.is_none_or(|(_, best_value)| value > best_value) {
+                            best = Some((i, value));
+                        }
+                    }
+                    None => {
+                        completed = false;
+                        break;
+                    }
+                }
+            }
+            let heuristic = search.heuristic;
+            if let Some((i, value)) = best {
+                // An incomplete search only improves on having no search at all.
+                if completed || result.depth == 0 {
+                    result.action = actions[i].clone();
+                    result.value = value;
+                }
+                if completed {
+                    result.depth = depth;
+                    result.solved = !heuristic;
+                    // Search the best action first at the next depth.
+                    let action = actions.remove(i);
+                    actions.insert(0, action);
+                }
+            }
+            if !completed || result.solved {
+                break;
+            }
+        }
+        self.stats.merge(&SearchStats {
+            nodes: limiter.nodes(),
+            max_depth: result.depth,
+            elapsed: limiter.elapsed(),
+            ..SearchStats::new()
+        });
+        result
+    }
+}
+
+/// The state of one depth of an iterative deepening search.
+struct Search<'a, 'b, E> {
+    limiter: &'a mut Limiter<'b>,
+    evaluator: &'a mut E,
+    /// Whether a heuristic evaluation was used, i.e. whether the values aren't exact.
+    heuristic: bool,
+}
+
+impl<E> Search<'_, '_, E> {
+    /// The value of action in state for the player to move, searching depth plies deep, or None
+    /// if the budget ran out.
+    fn action_value<G>(
+        &mut self,
+        state: &G,
+        action: &G::Action,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+    ) -> Option<f64>
+    where
+        G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+        E: Evaluator<G, Evaluation: Into<f64>>,
+    {
+        if !self.limiter.visit() {
+            return None;
+        }
+        let player = state.current_player();
+        match state.apply(action) {
+            Finished(_, outcome) => Some(outcome.payoff(&player)),
+            Ongoing(_) if depth <= 1 => {
+                self.heuristic = true;
+                Some(self.evaluator.evaluate(state, action).into())
+            }
+            Ongoing(next_state) => self
+                .negamax(&next_state, depth - 1, -beta, -alpha)
+                .map(|value| -value),
+        }
+    }
+
+    /// The value of state for the player to move, searching depth plies deep, or None if the
+    /// budget ran out.
+    fn negamax<G>(&mut self, state: &G, depth: usize, mut alpha: f64, beta: f64) -> Option<f64>
+    where
+        G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+        E: Evaluator<G, Evaluation: Into<f64>>,
+    {
+        let mut best = f64::NEG_INFINITY;
+        for action in state.legal_actions() {
+            let value = self.action_value(state, action, depth, alpha, beta)?;
+            best = best.max(value);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
+
+impl<G, E> Strategy<G, E> for AlphaBetaStrategy
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>>,
+    G::Action: Clone,
+    E: Evaluator<G, Evaluation: Into<f64>>,
+{
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        self.search(state, evaluator).action
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{atomic::AtomicBool, Arc},
+        time::Duration,
+    };
+
+    use crate::{
+        game_player::GamePlayer,
+        game_state::Features,
+        games::tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        learning::td_lambda::LinearValueEvaluator,
+        observer::SearchLog,
+        search::{alpha_beta::AlphaBetaStrategy, Budget},
+    };
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    /// Evaluates every non terminal action as 0.
+    fn neutral() -> LinearValueEvaluator {
+        LinearValueEvaluator::new(TicTacToe::N_FEATURES)
+    }
+
+    #[test]
+    fn solves_tic_tac_toe() {
+        let mut strategy = AlphaBetaStrategy::default();
+        let result = strategy.search(&position(), &mut neutral());
+        assert_eq!(result.action, ALL_ACTIONS[2]);
+        assert_eq!(result.value, 1.0);
+        assert!(result.solved);
+        let result = strategy.search(&TicTacToe::new(Piece::X), &mut neutral());
+        assert_eq!((result.value, result.depth, result.solved), (0.0, 9, true));
+    }
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let mut strategy = AlphaBetaStrategy::new(Budget {
+            depth: Some(2),
+            ..Default::default()
+        });
+        let result = strategy.search(&TicTacToe::new(Piece::X), &mut neutral());
+        assert_eq!((result.depth, result.solved), (2, false));
+        // Two plies are enough to see the win and that every other move lets O win or block.
+        let result = strategy.search(&position(), &mut neutral());
+        assert_eq!((result.action, result.value), (ALL_ACTIONS[2], 1.0));
+    }
+
+    #[test]
+    fn exhausted_budgets_still_play() {
+        let root = TicTacToe::new(Piece::X);
+        let cancel = Arc::new(AtomicBool::new(true));
+        let mut cancelled = AlphaBetaStrategy::new(Budget {
+            cancel: Some(cancel),
+            ..Default::default()
+        });
+        let result = cancelled.search(&root, &mut neutral());
+        assert_eq!((result.action, result.depth), (ALL_ACTIONS[0], 0));
+        let mut hurried = AlphaBetaStrategy::new(Budget::time(Duration::ZERO));
+        assert_eq!(hurried.search(&root, &mut neutral()).depth, 0);
+        // Depth 1 takes one node per opening move, and the rest of the budget runs out at
+        // depth 2.
+        let mut small = AlphaBetaStrategy::new(Budget::nodes(12));
+        assert_eq!(small.search(&root, &mut neutral()).depth, 1);
+        let mut small = AlphaBetaStrategy::new(Budget::nodes(3));
+        let result = small.search(&position(), &mut neutral());
+        assert_eq!((result.action, result.depth), (ALL_ACTIONS[2], 0));
+    }
+
+    #[test]
+    fn reports_its_nodes_per_move() {
+        let strategy = AlphaBetaStrategy::new(Budget::nodes(12));
+        let mut game_player = GamePlayer::new(TicTacToe::new(Piece::X), neutral(), strategy);
+        let mut log = SearchLog::new();
+        game_player.play_observed(&mut [&mut log]);
+        assert_eq!((log.moves[0].nodes, log.moves[0].max_depth), (12, 1));
+        assert!(log.moves.iter().all(|stats| stats.nodes <= 12));
+        assert_eq!(
+            log.total.nodes,
+            log.moves.iter().map(|stats| stats.nodes).sum()
+        );
+    }
+}
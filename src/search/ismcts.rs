@@ -0,0 +1,322 @@
+use crate::{
+    evaluator::Evaluator,
+    game_state::{outcome::Payoff, player::TwoPlayer, ApplyResult::*, Determinize, GameState},
+    rng::Rng,
+    search::{Budget, SearchStats},
+    strategy::{GreedyStrategy, Strategy},
+};
+
+/// The exploration constant used by IsmctsStrategy::new, suitable for payoffs in [-1, 1] such as
+/// those of WinDraw. Scale it with the range of the payoffs for other games.
+pub const DEFAULT_EXPLORATION: f64 = 1.4;
+
+/// A node of the search tree. Nodes are identified by the sequence of actions leading to them
+/// from the root, regardless of which determinization those actions were taken in. Thus a node
+/// stands for an information set of the searching player rather than for one concrete state.
+#[derive(Debug)]
+struct Node<A> {
+    /// The action leading to this node and the player who took it. None only for the root.
+    incoming: Option<(A, TwoPlayer)>,
+    children: Vec<usize>,
+    visits: u32,
+    /// The number of times this node could have been selected, i.e. the number of times its
+    /// action was legal in the determinization while we were selecting among its siblings.
+    availability: u32,
+    /// The total payoff from the perspective of the player who took the incoming action.
+    reward: f64,
+}
+
+impl<A> Node<A> {
+    fn new(incoming: Option<(A, TwoPlayer)>) -> Self {
+        Self {
+            incoming,
+            children: vec![],
+            visits: 0,
+            availability: 1,
+            reward: 0.0,
+        }
+    }
+
+    fn action(&self) -> &A {
+        &self.incoming.as_ref().expect("The root has no action.").0
+    }
+
+    fn ucb(&self, exploration: f64) -> f64 {
+        let visits = self.visits as f64;
+        self.reward / visits + exploration * ((self.availability as f64).ln() / visits).sqrt()
+    }
+}
+
+/// Single-observer Information Set Monte Carlo Tree Search.
+///
+/// Every iteration samples a state consistent with what the current player has observed (see
+/// Determinize), walks down a single tree shared by all iterations using UCB, expands one node,
+/// and finishes the game with a rollout. Because the tree is shared, the statistics of a node
+/// are averaged over all of the hidden states we might be in, so unlike MaskedEvaluator the cost
+/// of a search does not depend on how much of the game is hidden.
+///
+/// Rollouts are played with GreedyStrategy using the given Evaluator, so pairing this strategy
+/// with RandomEvaluator gives the usual uniformly random playouts.
+///
+/// The search runs until its Budget runs out, counting iterations as nodes, and always runs at
+/// least one iteration. As a Strategy it reports the iterations it ran as nodes.
+#[derive(Debug)]
+pub struct IsmctsStrategy {
+    budget: Budget,
+    exploration: f64,
+    rng: Rng,
+    stats: SearchStats,
+}
+
+impl IsmctsStrategy {
+    pub fn new(iterations: usize, seed: u64) -> Self {
+        Self::with_exploration(iterations, DEFAULT_EXPLORATION, seed)
+    }
+
+    pub fn with_exploration(iterations: usize, exploration: f64, seed: u64) -> Self {
+        Self::with_budget(Budget::nodes(iterations as u64), exploration, seed)
+    }
+
+    pub fn with_budget(budget: Budget, exploration: f64, seed: u64) -> Self {
+        Self {
+            budget,
+            exploration,
+            rng: Rng::new(seed),
+            stats: SearchStats::new(),
+        }
+    }
+
+    /// Runs the search from the given state and returns every action tried at the root together
+    /// with the number of times it was visited.
+    pub fn search<G, E>(&mut self, state: &G, evaluator: &mut E) -> Vec<(G::Action, u32)>
+    where
+        G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Determinize,
+        G::Action: Clone + PartialEq,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+    {
+        let budget = self.budget.clone();
+        let mut limiter = budget.start();
+        let mut tree = vec![Node::new(None)];
+        loop {
+            self.iterate(&mut tree, state, evaluator);
+            limiter.count();
+            if limiter.is_exhausted() {
+                break;
+            }
+        }
+        self.stats.merge(&SearchStats {
+            nodes: limiter.nodes(),
+            elapsed: limiter.elapsed(),
+            ..SearchStats::new()
+        });
+        tree[0]
+            .children
+            .iter()
+            .map(|&child| (tree[child].action().clone(), tree[child].visits))
+            .collect()
+    }
+
+    fn iterate<G, E>(&mut self, tree: &mut Vec<Node<G::Action>>, state: &G, evaluator: &mut E)
+    where
+        G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Determinize,
+        G::Action: Clone + PartialEq,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+    {
+        let mut state = state.determinize(&mut self.rng);
+        let mut path = vec![];
+        let mut node = 0;
+        let outcome = loop {
+            let legal: Vec<G::Action> = state.legal_actions().cloned().collect();
+            assert!(
+                !legal.is_empty(),
+                "Game isn't over but there were no legal moves available."
+            );
+            let untried: Vec<&G::Action> = legal
+                .iter()
+                .filter(|&action| {
+                    !tree[node]
+                        .children
+                        .iter()
+                        .any(|&child| tree[child].action() == action)
+                })
+                .collect();
+            // Expand one untried action if there is any, otherwise select among the children that
+            // are legal in this determinization.
+            let (child, expanded) = if let Some(&action) = self.rng.choose(&untried) {
+                let child = tree.len();
+                tree.push(Node::new(Some((action.clone(), state.current_player()))));
+                tree[node].children.push(child);
+                (child, true)
+            } else {
+                let available: Vec<usize> = tree[node]
+                    .children
+                    .iter()
+                    .copied()
+                    .filter(|&child| legal.contains(tree[child].action()))
+                    .collect();
+                available
+                    .iter()
+                    .for_each(|&child| tree[child].availability += 1);
+                let best = available
+                    .into_iter()
+                    .max_by(|&a, &b| {
+                        tree[a]
+                            .ucb(self.exploration)
+                            .total_cmp(&tree[b].ucb(self.exploration))
+                    })
+                    .expect("Every legal action has been tried, so there is a child.");
+                (best, false)
+            };
+            path.push(child);
+            match state.apply(tree[child].action()) {
+                Finished(_, outcome) => break outcome,
+                Ongoing(next_state) if expanded => break self.rollout(next_state, evaluator),
+                Ongoing(next_state) => state = next_state,
+            }
+            node = child;
+        };
+        for node in path {
+            let node = &mut tree[node];
+            let (_, player) = node
+                .incoming
+                .as_ref()
+                .expect("The root is never on the path.");
+            node.reward += outcome.payoff(player);
+            node.visits += 1;
+        }
+    }
+
+    fn rollout<G, E>(&mut self, mut state: G, evaluator: &mut E) -> G::Outcome
+    where
+        G: GameState,
+        G::Action: Clone,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+    {
+        loop {
+            let action = GreedyStrategy.choose(&state, evaluator);
+            match state.apply(&action) {
+                Ongoing(next_state) => state = next_state,
+                Finished(_, outcome) => return outcome,
+            }
+        }
+    }
+}
+
+impl<G, E> Strategy<G, E> for IsmctsStrategy
+where
+    G: GameState<Player = TwoPlayer, Outcome: Payoff<TwoPlayer>> + Determinize,
+    G::Action: Clone + PartialEq,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+{
+    /// Returns the most visited action at the root.
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        self.search(state, evaluator)
+            .into_iter()
+            .max_by_key(|(_, visits)| *visits)
+            .map(|(action, _)| action)
+            .expect("Game isn't over but there were no legal moves available.")
+    }
+
+    fn take_stats(&mut self) -> SearchStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        evaluator::RandomEvaluator,
+        game_player::{Match, StrategyAgent},
+        game_state::{ApplyResult, GameState},
+        games::{
+            kuhn_poker::Card,
+            leduc_holdem::{Action, LeducHoldem},
+            masked_tic_tac_toe::MaskedTicTacToe,
+            tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        },
+        observer::SearchLog,
+        search::{
+            ismcts::{IsmctsStrategy, DEFAULT_EXPLORATION},
+            Budget,
+        },
+        strategy::{GreedyStrategy, Strategy},
+    };
+
+    #[test]
+    fn takes_immediate_win() {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        let mut strategy = IsmctsStrategy::new(2000, 1);
+        let action = strategy.choose(&game, &mut RandomEvaluator::new(2));
+        assert_eq!(action, ALL_ACTIONS[2]);
+    }
+
+    #[test]
+    fn plays_legal_moves_with_full_mask() {
+        let mut game = MaskedTicTacToe::new(ALL_ACTIONS);
+        let mut strategy = IsmctsStrategy::new(200, 3);
+        let mut evaluator = RandomEvaluator::new(4);
+        for _ in 0..4 {
+            let action = strategy.choose(&game, &mut evaluator);
+            assert!(game.is_legal(&action));
+            game.apply_unchecked_mut(&action);
+        }
+    }
+
+    #[test]
+    fn does_not_fold_the_nuts_in_leduc() {
+        let mut game = LeducHoldem::deal(Card::King, Card::Jack, Card::King);
+        for action in [Action::Call, Action::Call, Action::Call, Action::Raise] {
+            game = match game.apply(&action) {
+                ApplyResult::Ongoing(next_state) => next_state,
+                _ => unreachable!(),
+            };
+        }
+        let mut strategy = IsmctsStrategy::with_exploration(500, 10.0, 5);
+        let action = strategy.choose(&game, &mut RandomEvaluator::new(6));
+        assert_ne!(action, Action::Fold);
+    }
+
+    #[test]
+    fn budgets_bound_the_search() {
+        let game = TicTacToe::new(Piece::X);
+        let mut limited = IsmctsStrategy::new(300, 7);
+        let visits: u32 = limited
+            .search(&game, &mut RandomEvaluator::new(1))
+            .iter()
+            .map(|(_, visits)| visits)
+            .sum();
+        assert_eq!(visits, 300);
+        // A zero time budget, or an empty one, still runs and counts one iteration.
+        for budget in [Budget::time(Duration::ZERO), Budget::nodes(0)] {
+            let mut hurried = IsmctsStrategy::with_budget(budget, DEFAULT_EXPLORATION, 7);
+            assert_eq!(hurried.search(&game, &mut RandomEvaluator::new(1)).len(), 1);
+            let stats = Strategy::<TicTacToe, RandomEvaluator>::take_stats(&mut hurried);
+            assert_eq!(stats.nodes, 1);
+        }
+    }
+
+    #[test]
+    fn reports_its_iterations_per_move() {
+        let mut game_match = Match::new(
+            TicTacToe::new(Piece::X),
+            StrategyAgent::new(RandomEvaluator::new(1), IsmctsStrategy::new(50, 2)),
+            StrategyAgent::new(RandomEvaluator::new(3), GreedyStrategy),
+        );
+        let mut log = SearchLog::new();
+        game_match.play_observed(&mut [&mut log]);
+        let nodes: Vec<u64> = log.moves.iter().map(|stats| stats.nodes).collect();
+        assert!(nodes.len() >= 5);
+        for (ply, nodes) in nodes.into_iter().enumerate() {
+            assert_eq!(nodes, if ply.is_multiple_of(2) { 50 } else { 0 });
+        }
+    }
+}
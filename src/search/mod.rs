@@ -0,0 +1,213 @@
+pub mod alpha_beta;
+pub mod ismcts;
+pub mod pimc;
+
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Limits on how long a search may run. Every limit is optional, and the default is unlimited.
+/// Anytime searches stop as soon as any limit is reached and return the best result found so
+/// far.
+///
+/// A search may overshoot the time limit by the time it takes to finish the node, or MCTS
+/// iteration, it is working on. Searches always do enough work to return some legal action.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub time: Option<Duration>,
+    /// The number of nodes a depth-first search may visit, the number of iterations of MCTS, or
+    /// the number of worlds PIMC samples.
+    pub nodes: Option<u64>,
+    /// The deepest ply a depth-first search looks at. Ignored by MCTS.
+    pub depth: Option<usize>,
+    /// Another thread can set this to stop the search.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    /// A budget that only limits the number of nodes or iterations.
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    /// A budget that only limits the time.
+    pub fn time(time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+
+    /// Starts the clock on a search with this budget.
+    pub fn start(&self) -> Limiter<'_> {
+        Limiter {
+            budget: self,
+            started: Instant::now(),
+            nodes: 0,
+        }
+    }
+}
+
+/// Tracks a running search against its Budget.
+#[derive(Debug, Clone)]
+pub struct Limiter<'a> {
+    budget: &'a Budget,
+    started: Instant,
+    nodes: u64,
+}
+
+impl Limiter<'_> {
+    /// Counts a node, or an iteration, and returns false if the budget was already used up, in
+    /// which case the search should stop instead of visiting it.
+    pub fn visit(&mut self) -> bool {
+        if self.is_exhausted() {
+            return false;
+        }
+        self.count();
+        true
+    }
+
+    /// Counts a node, or an iteration, that the search visits whatever the budget, like the
+    /// first iteration of a sampling search, which it needs to have a move to play.
+    pub fn count(&mut self) {
+        self.nodes += 1;
+    }
+
+    /// Returns true if any limit other than depth has been reached.
+    pub fn is_exhausted(&self) -> bool {
+        self.budget.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self
+                .budget
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            || self
+                .budget
+                .time
+                .is_some_and(|time| self.started.elapsed() >= time)
+    }
+
+    pub fn depth(&self) -> Option<usize> {
+        self.budget.depth
+    }
+
+    /// The number of nodes or iterations counted so far.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Counts of the work an evaluator or search did, from Evaluator::take_stats. Counters that an
+/// evaluator doesn't track stay 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchStats {
+    /// The number of states or actions the search evaluated, including the top level calls.
+    pub nodes: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// The number of entries in the cache when the statistics were taken.
+    pub cache_size: usize,
+    /// The deepest recursion, where a top level call has depth 1.
+    pub max_depth: usize,
+    /// The time spent in top level calls.
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn nodes_per_second(&self) -> f64 {
+        self.nodes as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The fraction of cache lookups that were hits.
+    pub fn hit_rate(&self) -> f64 {
+        self.cache_hits as f64 / (self.cache_hits + self.cache_misses) as f64
+    }
+
+    /// Adds the work in other, which was taken later, to this, e.g. to total the statistics of
+    /// every move of a game.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.cache_size = other.cache_size;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.elapsed += other.elapsed;
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} hits, {} misses, {} cached, depth {}, {:.3?}",
+            self.nodes,
+            self.cache_hits,
+            self.cache_misses,
+            self.cache_size,
+            self.max_depth,
+            self.elapsed
+        )?;
+        if !self.elapsed.is_zero() {
+            write!(f, ", {:.0} nodes/s", self.nodes_per_second())?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the SearchStats of a recursive evaluator. Call enter at the start of every evaluation,
+/// including the recursive ones, and exit at the end. The clock runs while a top level call is
+/// in progress.
+#[derive(Debug, Clone, Default)]
+pub struct StatsTracker {
+    pub stats: SearchStats,
+    depth: usize,
+    started: Option<Instant>,
+}
+
+impl StatsTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn enter(&mut self) {
+        if self.depth == 0 {
+            self.started = Some(Instant::now());
+        }
+        self.depth += 1;
+        self.stats.nodes += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+    }
+
+    pub fn exit(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Some(started) = self.started.take() {
+                self.stats.elapsed += started.elapsed();
+            }
+        }
+    }
+
+    /// Returns the statistics since the last take, with the given cache size, and starts
+    /// counting again.
+    pub fn take(&mut self, cache_size: usize) -> SearchStats {
+        let mut stats = std::mem::take(&mut self.stats);
+        stats.cache_size = cache_size;
+        stats
+    }
+}
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+
+use crate::{
+    evaluator::Evaluator,
+    game_state::{Determinize, GameState},
+    rng::Rng,
+    search::Budget,
+    strategy::Strategy,
+};
+
+/// Perfect Information Monte Carlo.
+///
+/// Samples a number of states consistent with what the current player has observed (see
+/// Determinize) and evaluates every legal action in each of them with the given Evaluator as if
+/// the Game had perfect information, e.g. with MinimaxEvaluator. Each sampled world then votes
+/// for its best action(s), splitting its vote evenly between ties, and the action with the most
+/// votes is chosen.
+///
+/// This is cheap and often strong, but it is not sound: each world is solved assuming that
+/// everyone can see everything, so it can neither value information nor hide it.
+///
+/// Sampling runs until its Budget runs out, counting samples as nodes, and always samples at
+/// least one world.
+#[derive(Debug)]
+pub struct PimcStrategy {
+    budget: Budget,
+    rng: Rng,
+}
+
+impl PimcStrategy {
+    pub fn new(samples: usize, seed: u64) -> Self {
+        Self::with_budget(Budget::nodes(samples as u64), seed)
+    }
+
+    pub fn with_budget(budget: Budget, seed: u64) -> Self {
+        Self {
+            budget,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Returns every legal action together with the number of votes it received, in the order of
+    /// legal_actions(). The votes sum to the number of worlds sampled.
+    pub fn votes<G, E>(&mut self, state: &G, evaluator: &mut E) -> Vec<(G::Action, f64)>
+    where
+        G: GameState + Determinize,
+        G::Action: Clone,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+    {
+        let mut votes: Vec<(G::Action, f64)> = state
+            .legal_actions()
+            .map(|action| (action.clone(), 0.0))
+            .collect();
+        let budget = self.budget.clone();
+        let mut limiter = budget.start();
+        loop {
+            let world = state.determinize(&mut self.rng);
+            let evals: Vec<E::Evaluation> = votes
+                .iter()
+                .map(|(action, _)| evaluator.evaluate(&world, action))
+                .collect();
+            let mut best = vec![0];
+            for (i, eval) in evals.iter().enumerate().skip(1) {
+                match evals[best[0]].partial_cmp(eval) {
+                    Some(Ordering::Less) => best = vec![i],
+                    Some(Ordering::Equal) => best.push(i),
+                    Some(Ordering::Greater) => (),
+                    None => panic!("Evaluator returned an evaluation that couldn't be compared"),
+                }
+            }
+            let share = 1.0 / best.len() as f64;
+            best.into_iter().for_each(|i| votes[i].1 += share);
+            limiter.count();
+            if limiter.is_exhausted() {
+                break;
+            }
+        }
+        votes
+    }
+}
+
+impl<G, E> Strategy<G, E> for PimcStrategy
+where
+    G: GameState + Determinize,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+{
+    /// Returns the action with the most votes, preferring the earliest one on ties.
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        let mut votes = self.votes(state, evaluator).into_iter();
+        let (mut best_action, mut best_votes) = votes
+            .next()
+            .expect("Game isn't over but there were no legal moves available.");
+        for (action, votes) in votes {
+            if votes > best_votes {
+                best_action = action;
+                best_votes = votes;
+            }
+        }
+        best_action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        evaluator::{Evaluator, MinimaxEvaluator},
+        games::{
+            masked_tic_tac_toe::MaskedTicTacToe,
+            tic_tac_toe::{Piece, TicTacToe, ALL_ACTIONS},
+        },
+        search::{pimc::PimcStrategy, Budget},
+        strategy::Strategy,
+    };
+
+    #[test]
+    fn agrees_with_minimax_under_perfect_information() {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [4, 0, 8] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        let mut evaluator = MinimaxEvaluator::new();
+        let votes = PimcStrategy::new(3, 0).votes(&game, &mut evaluator);
+        let evals: Vec<i8> = votes
+            .iter()
+            .map(|(action, _)| evaluator.evaluate(&game, action))
+            .collect();
+        let best = *evals.iter().max().unwrap();
+        for ((_, votes), eval) in votes.into_iter().zip(evals) {
+            assert_eq!(votes > 0.0, eval == best);
+        }
+    }
+
+    #[test]
+    fn votes_over_masked_worlds() {
+        let mut game = MaskedTicTacToe::new([ALL_ACTIONS[0], ALL_ACTIONS[1]]);
+        for action in [0, 4, 8, 1] {
+            game.apply_unchecked_mut(&ALL_ACTIONS[action]);
+        }
+        let mut strategy = PimcStrategy::new(10, 5);
+        let mut evaluator = MinimaxEvaluator::new();
+        let votes = strategy.votes(&game, &mut evaluator);
+        assert_eq!(votes.len(), game.legal_actions().count());
+        assert!((votes.iter().map(|(_, v)| v).sum::<f64>() - 10.0).abs() < 1e-9);
+        let action = strategy.choose(&game, &mut evaluator);
+        assert!(game.is_legal(&action));
+        // A zero time budget, or an empty one, still samples one world.
+        for budget in [Budget::time(Duration::ZERO), Budget::nodes(0)] {
+            let mut hurried = PimcStrategy::with_budget(budget, 5);
+            let votes = hurried.votes(&game, &mut evaluator);
+            assert!((votes.iter().map(|(_, v)| v).sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+}
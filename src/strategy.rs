@@ -2,7 +2,12 @@ use std::cmp::Ordering;
 use std::ops::Deref;
 
 use crate::evaluator::*;
+use crate::game_state::error::{GameError, GameResult};
 use crate::game_state::*;
+use crate::learning::Schedule;
+use crate::policy;
+use crate::rng::Rng;
+use crate::search::SearchStats;
 
 /// The trait for strategies. Given a DynamicGameState, return either an Action or a GameError.
 /// Strategies can use the output of the Evaluator in very different ways. For instance, you may
@@ -20,6 +25,13 @@ where
     E: Evaluator<G>,
 {
     fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action;
+
+    /// Returns the statistics of the searching the strategy did itself since the last call, such
+    /// as the nodes it counted against a Budget, and starts counting again. Strategies that
+    /// don't search report nothing.
+    fn take_stats(&mut self) -> SearchStats {
+        SearchStats::new()
+    }
 }
 
 /// For some games, it is natural to return a reference to an action that was generated by
@@ -41,7 +53,7 @@ impl<'a, A> Deref for ActionRef<'a, A> {
     fn deref(&self) -> &Self::Target {
         match self {
             ActionRef::Owned(a) => a,
-            ActionRef::Borrowed(a) => *a,
+            ActionRef::Borrowed(a) => a,
         }
     }
 }
@@ -49,6 +61,9 @@ impl<'a, A> Deref for ActionRef<'a, A> {
 /// Takes an Evaluator whose Evaluation impl's PartialOrd and returns the action that has the highest
 /// Evaluation. This strategy is optimal for zero sum two player finite games w perfect
 /// information; the only catch is computing the evaluations ;).
+///
+/// Ties go to the action that comes first in legal_actions. Use TieBreakingStrategy to break
+/// them some other way, and rank to see every action's evaluation.
 pub struct GreedyStrategy;
 
 impl<G, E> Strategy<G, E> for GreedyStrategy
@@ -78,3 +93,489 @@ where
         best_action.clone()
     }
 }
+
+impl GreedyStrategy {
+    /// Like choose, but returns NoLegalActions or EvaluatorFailure instead of panicking.
+    pub fn try_choose<G, E>(&self, state: &G, evaluator: &mut E) -> GameResult<G::Action, G>
+    where
+        G: GameState + Clone,
+        G::Action: Clone,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+    {
+        TieBreakingStrategy::new(FirstTie).try_choose(state, evaluator)
+    }
+}
+
+/// Legal actions with their evaluations, best first, as returned by rank.
+pub type Ranking<G, E> = Vec<(<G as GameState>::Action, <E as Evaluator<G>>::Evaluation)>;
+
+/// Returns every legal action with its evaluation, best first. Actions with equal evaluations
+/// keep the order of legal_actions, so the first action is the one GreedyStrategy plays.
+///
+/// Returns NoLegalActions if there are no legal actions, and EvaluatorFailure with the two
+/// actions involved if two evaluations turn out to be incomparable, e.g. because one is NaN.
+pub fn rank<G, E>(state: &G, evaluator: &mut E) -> GameResult<Ranking<G, E>, G>
+where
+    G: GameState + Clone,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+{
+    let ranking = rank_actions(state, state.legal_actions(), evaluator)?;
+    if ranking.is_empty() {
+        return Err(GameError::NoLegalActions(state.clone()));
+    }
+    Ok(ranking)
+}
+
+/// Ranks the given actions by insertion sort, which is stable and checks every comparison it
+/// makes, unlike sort_by with a partial_cmp that is unwrapped.
+fn rank_actions<'a, G, E>(
+    state: &G,
+    actions: impl IntoIterator<Item = &'a G::Action>,
+    evaluator: &mut E,
+) -> GameResult<Ranking<G, E>, G>
+where
+    G: GameState + Clone,
+    G::Action: Clone + 'a,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+{
+    let mut ranking: Ranking<G, E> = vec![];
+    for action in actions {
+        let eval = evaluator.evaluate(state, action);
+        let mut position = ranking.len();
+        for (i, (other, other_eval)) in ranking.iter().enumerate() {
+            match other_eval.partial_cmp(&eval) {
+                Some(Ordering::Less) => {
+                    position = i;
+                    break;
+                }
+                Some(_) => (),
+                None => {
+                    return Err(GameError::EvaluatorFailure(
+                        state.clone(),
+                        vec![other.clone(), action.clone()],
+                    ))
+                }
+            }
+        }
+        ranking.insert(position, (action.clone(), eval));
+    }
+    Ok(ranking)
+}
+
+/// The leading actions of a non empty ranking whose evaluations equal the best one.
+fn best_ties<A, V>(ranking: Vec<(A, V)>) -> Vec<A>
+where
+    V: PartialOrd,
+{
+    let mut ranking = ranking.into_iter();
+    let (best, best_eval) = ranking.next().expect("The ranking isn't empty.");
+    let mut tied = vec![best];
+    tied.extend(
+        ranking
+            .take_while(|(_, eval)| eval.partial_cmp(&best_eval) == Some(Ordering::Equal))
+            .map(|(action, _)| action),
+    );
+    tied
+}
+
+/// Panics with a message describing why a strategy couldn't choose an action.
+fn expect_action<G>(result: GameResult<G::Action, G>) -> G::Action
+where
+    G: GameState,
+{
+    match result {
+        Ok(action) => action,
+        Err(GameError::NoLegalActions(_)) => {
+            panic!("Game isn't over but there were no legal moves available.")
+        }
+        Err(GameError::EvaluatorFailure(..)) => {
+            panic!("Evaluator returned an evaluation that couldn't be compared")
+        }
+        Err(_) => panic!("The strategy couldn't choose an action."),
+    }
+}
+
+/// Decides between actions whose evaluations are equally good, for TieBreakingStrategy.
+pub trait TieBreak<G>
+where
+    G: GameState,
+{
+    /// Picks one of tied, which holds at least one action, in the order of legal_actions.
+    fn break_tie(&mut self, state: &G, tied: Vec<G::Action>) -> GameResult<G::Action, G>;
+}
+
+/// Plays the first of the tied actions, like GreedyStrategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirstTie;
+
+impl<G> TieBreak<G> for FirstTie
+where
+    G: GameState,
+{
+    fn break_tie(&mut self, _state: &G, tied: Vec<G::Action>) -> GameResult<G::Action, G> {
+        Ok(tied.into_iter().next().expect("There is a tied action."))
+    }
+}
+
+/// Plays one of the tied actions uniformly at random, which removes the bias of FirstTie toward
+/// actions that come early in legal_actions.
+#[derive(Debug, Clone)]
+pub struct RandomTie {
+    rng: Rng,
+}
+
+impl RandomTie {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G> TieBreak<G> for RandomTie
+where
+    G: GameState,
+{
+    fn break_tie(&mut self, _state: &G, mut tied: Vec<G::Action>) -> GameResult<G::Action, G> {
+        let i = self.rng.gen_index(tied.len());
+        Ok(tied.swap_remove(i))
+    }
+}
+
+/// Ranks the tied actions with a second evaluator and plays the best, or the first of those
+/// that are still tied. Useful for cheap heuristics, e.g. preferring the quickest win among the
+/// actions a solver says are winning.
+#[derive(Debug, Clone)]
+pub struct SecondaryTie<E> {
+    pub evaluator: E,
+}
+
+impl<E> SecondaryTie<E> {
+    pub fn new(evaluator: E) -> Self {
+        Self { evaluator }
+    }
+}
+
+impl<G, E> TieBreak<G> for SecondaryTie<E>
+where
+    G: GameState + Clone,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+{
+    fn break_tie(&mut self, state: &G, tied: Vec<G::Action>) -> GameResult<G::Action, G> {
+        let ranking = rank_actions(state, &tied, &mut self.evaluator)?;
+        Ok(ranking
+            .into_iter()
+            .next()
+            .expect("There is a tied action.")
+            .0)
+    }
+}
+
+/// Plays an action with the highest evaluation like GreedyStrategy, but lets a TieBreak decide
+/// between equally good actions.
+#[derive(Debug, Clone)]
+pub struct TieBreakingStrategy<T> {
+    pub tie_break: T,
+}
+
+impl<T> TieBreakingStrategy<T> {
+    pub fn new(tie_break: T) -> Self {
+        Self { tie_break }
+    }
+
+    /// Like choose, but returns NoLegalActions or EvaluatorFailure instead of panicking.
+    pub fn try_choose<G, E>(&mut self, state: &G, evaluator: &mut E) -> GameResult<G::Action, G>
+    where
+        G: GameState + Clone,
+        G::Action: Clone,
+        E: Evaluator<G>,
+        E::Evaluation: PartialOrd,
+        T: TieBreak<G>,
+    {
+        let tied = best_ties(rank(state, evaluator)?);
+        self.tie_break.break_tie(state, tied)
+    }
+}
+
+impl<G, E, T> Strategy<G, E> for TieBreakingStrategy<T>
+where
+    G: GameState + Clone,
+    G::Action: Clone,
+    E: Evaluator<G>,
+    E::Evaluation: PartialOrd,
+    T: TieBreak<G>,
+{
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        expect_action(self.try_choose(state, evaluator))
+    }
+}
+
+/// Converts every legal action's evaluation to a float. NaN evaluations become negative infinity,
+/// so they are never preferred and never make a strategy panic.
+fn numeric_evaluations<'a, G, E>(state: &'a G, evaluator: &mut E) -> Vec<(&'a G::Action, f64)>
+where
+    G: GameState,
+    E: Evaluator<G, Evaluation: Into<f64>>,
+{
+    let evaluations: Vec<(&G::Action, f64)> = state
+        .legal_actions()
+        .map(|action| {
+            let value: f64 = evaluator.evaluate(state, action).into();
+            (
+                action,
+                if value.is_nan() {
+                    f64::NEG_INFINITY
+                } else {
+                    value
+                },
+            )
+        })
+        .collect();
+    assert!(
+        !evaluations.is_empty(),
+        "Game isn't over but there were no legal moves available."
+    );
+    evaluations
+}
+
+/// One of the actions with the highest evaluation, chosen uniformly at random.
+fn best_at_random<'a, A>(evaluations: &[(&'a A, f64)], rng: &mut Rng) -> &'a A {
+    let best = evaluations
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let best_actions: Vec<&A> = evaluations
+        .iter()
+        .filter(|(_, value)| *value == best)
+        .map(|(action, _)| *action)
+        .collect();
+    rng.choose(&best_actions).expect("There is a best action.")
+}
+
+/// With probability epsilon plays a uniformly random legal action, and otherwise one of the
+/// actions with the highest evaluation, breaking ties at random.
+///
+/// Epsilon follows a Schedule evaluated at step, which choose increments. Set step directly to
+/// anneal by some other count, such as the number of games played.
+#[derive(Debug, Clone)]
+pub struct EpsilonGreedyStrategy {
+    pub epsilon: Schedule,
+    pub step: usize,
+    rng: Rng,
+}
+
+impl EpsilonGreedyStrategy {
+    pub fn new(epsilon: Schedule, seed: u64) -> Self {
+        Self {
+            epsilon,
+            step: 0,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G, E> Strategy<G, E> for EpsilonGreedyStrategy
+where
+    G: GameState,
+    G::Action: Clone,
+    E: Evaluator<G, Evaluation: Into<f64>>,
+{
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        let epsilon = self.epsilon.value(self.step);
+        self.step += 1;
+        if self.rng.gen_f64() < epsilon {
+            let actions: Vec<&G::Action> = state.legal_actions().collect();
+            return (*self
+                .rng
+                .choose(&actions)
+                .expect("Game isn't over but there were no legal moves available."))
+            .clone();
+        }
+        let evaluations = numeric_evaluations(state, evaluator);
+        best_at_random(&evaluations, &mut self.rng).clone()
+    }
+}
+
+/// Plays each legal action with probability proportional to exp(evaluation / temperature), also
+/// known as Boltzmann exploration. High temperatures play almost uniformly, and as the
+/// temperature approaches 0 it plays greedily. A temperature of 0 plays one of the best actions,
+/// breaking ties at random.
+///
+/// The temperature follows a Schedule evaluated at step, which choose increments, and each choice
+/// samples from the distribution of a SoftmaxPolicy at that temperature.
+#[derive(Debug, Clone)]
+pub struct SoftmaxStrategy {
+    pub temperature: Schedule,
+    pub step: usize,
+    rng: Rng,
+}
+
+impl SoftmaxStrategy {
+    pub fn new(temperature: Schedule, seed: u64) -> Self {
+        Self {
+            temperature,
+            step: 0,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<G, E> Strategy<G, E> for SoftmaxStrategy
+where
+    G: GameState,
+    G::Action: Clone,
+    E: Evaluator<G, Evaluation: Into<f64>>,
+{
+    fn choose(&mut self, state: &G, evaluator: &mut E) -> G::Action {
+        let temperature = self.temperature.value(self.step);
+        self.step += 1;
+        let evaluations = numeric_evaluations(state, evaluator);
+        let values: Vec<f64> = evaluations.iter().map(|(_, value)| *value).collect();
+        let distribution = policy::softmax(&values, temperature);
+        evaluations[self.rng.gen_weighted_index(&distribution)]
+            .0
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{Evaluator, MinimaxEvaluator},
+        game_state::error::GameError,
+        games::tic_tac_toe::{Action, Piece, TicTacToe, ALL_ACTIONS},
+        learning::Schedule,
+        strategy::{
+            rank, EpsilonGreedyStrategy, FirstTie, GreedyStrategy, RandomTie, SecondaryTie,
+            SoftmaxStrategy, Strategy, TieBreakingStrategy,
+        },
+    };
+
+    /// Scores one square with the given value and every other square 0.
+    struct SquareEvaluator(Action, f64);
+
+    impl Evaluator<TicTacToe> for SquareEvaluator {
+        type Evaluation = f64;
+
+        fn evaluate(&mut self, _state: &TicTacToe, action: &Action) -> f64 {
+            if *action == self.0 {
+                self.1
+            } else {
+                0.0
+            }
+        }
+    }
+
+    /// X to move with two in a row, but O threatens to win as well.
+    fn position() -> TicTacToe {
+        let mut game = TicTacToe::new(Piece::X);
+        for action in [0, 3, 1, 4] {
+            game.apply_mut(&ALL_ACTIONS[action]);
+        }
+        game
+    }
+
+    fn count_wins<S>(strategy: &mut S, games: usize) -> usize
+    where
+        S: Strategy<TicTacToe, MinimaxEvaluator<TicTacToe>>,
+    {
+        let mut minimax = MinimaxEvaluator::new();
+        (0..games)
+            .filter(|_| strategy.choose(&position(), &mut minimax) == ALL_ACTIONS[2])
+            .count()
+    }
+
+    #[test]
+    fn epsilon_greedy_anneals() {
+        let epsilon = Schedule::Linear {
+            start: 1.0,
+            end: 0.0,
+            steps: 500,
+        };
+        let mut strategy = EpsilonGreedyStrategy::new(epsilon, 1);
+        // Mostly random at first: the winning move is one of five.
+        let early = count_wins(&mut strategy, 100);
+        assert!(early < 50, "{}", early);
+        strategy.step = 500;
+        assert_eq!(count_wins(&mut strategy, 100), 100);
+        // The same seed plays the same moves.
+        let mut again = EpsilonGreedyStrategy::new(epsilon, 1);
+        assert_eq!(count_wins(&mut again, 100), early);
+    }
+
+    #[test]
+    fn softmax_temperature() {
+        let mut hot = SoftmaxStrategy::new(Schedule::Constant(100.0), 2);
+        let wins = count_wins(&mut hot, 1000);
+        assert!((150..250).contains(&wins), "{}", wins);
+        let mut cold = SoftmaxStrategy::new(Schedule::Constant(0.05), 2);
+        assert_eq!(count_wins(&mut cold, 100), 100);
+        let mut greedy = SoftmaxStrategy::new(Schedule::Constant(0.0), 2);
+        assert_eq!(count_wins(&mut greedy, 100), 100);
+    }
+
+    #[test]
+    fn rank_is_sorted_best_first() {
+        let mut minimax = MinimaxEvaluator::new();
+        let ranking = rank(&position(), &mut minimax).unwrap();
+        assert_eq!(ranking.len(), 5);
+        assert_eq!(ranking[0], (ALL_ACTIONS[2], 1));
+        assert!(ranking.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        // Blocking O at 5 draws and the rest lose, so they keep the order of legal_actions.
+        let losing: Vec<Action> = ranking[1..].iter().map(|(action, _)| *action).collect();
+        assert_eq!(
+            losing,
+            [5, 6, 7, 8].map(|i| ALL_ACTIONS[i]),
+            "{:?}",
+            ranking
+        );
+    }
+
+    #[test]
+    fn tie_breaks() {
+        // Every opening move draws.
+        let root = TicTacToe::new(Piece::X);
+        let mut minimax = MinimaxEvaluator::new();
+        assert_eq!(GreedyStrategy.choose(&root, &mut minimax), ALL_ACTIONS[0]);
+        let mut first = TieBreakingStrategy::new(FirstTie);
+        assert_eq!(first.choose(&root, &mut minimax), ALL_ACTIONS[0]);
+        let mut random = TieBreakingStrategy::new(RandomTie::new(3));
+        let mut seen = [false; 9];
+        for _ in 0..200 {
+            let action = random.choose(&root, &mut minimax);
+            seen[ALL_ACTIONS.iter().position(|a| *a == action).unwrap()] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+        let mut center =
+            TieBreakingStrategy::new(SecondaryTie::new(SquareEvaluator(ALL_ACTIONS[4], 1.0)));
+        assert_eq!(center.choose(&root, &mut minimax), ALL_ACTIONS[4]);
+        // The secondary evaluator only decides between the primary's best actions.
+        let mut loser =
+            TieBreakingStrategy::new(SecondaryTie::new(SquareEvaluator(ALL_ACTIONS[5], 1.0)));
+        assert_eq!(loser.choose(&position(), &mut minimax), ALL_ACTIONS[2]);
+    }
+
+    #[test]
+    fn incomparable_evaluations_are_errors() {
+        let mut nan = SquareEvaluator(ALL_ACTIONS[6], f64::NAN);
+        let error = GreedyStrategy.try_choose(&position(), &mut nan);
+        match error {
+            Err(GameError::EvaluatorFailure(_, actions)) => {
+                assert!(actions.contains(&ALL_ACTIONS[6]))
+            }
+            _ => panic!("Expected an EvaluatorFailure."),
+        }
+        assert!(rank(&position(), &mut nan).is_err());
+        let mut fine = SquareEvaluator(ALL_ACTIONS[6], 1.0);
+        assert_eq!(
+            GreedyStrategy.try_choose(&position(), &mut fine).ok(),
+            Some(ALL_ACTIONS[6])
+        );
+    }
+}